@@ -0,0 +1,113 @@
+use crate::installer::{files_with_destinations, hash_file, list_root_type_files, DataDirPathExt};
+use crate::models::AsVersion;
+use crate::receipt::{owned_paths, Receipt, ReceiptFile};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AdoptResult {
+    /// インストール済みとして記録したマニフェスト。
+    pub adopted: Vec<crate::models::ManifestId>,
+    /// どのマニフェストにも一致しなかったファイル。
+    pub unmatched: Vec<std::path::PathBuf>,
+}
+
+/// 手動でインストールされたファイルを探し、`candidates`のいずれかと一致すればレシートを書き込む。
+///
+/// `installed`は既にインストール済みのマニフェストで、これらが所有するファイルは無視されます。
+/// 一致したマニフェストと、どれにも一致しなかったファイルを返します。
+pub async fn scan_and_adopt(
+    root: &std::path::Path,
+    installed: &[crate::models::Manifest],
+    candidates: &[crate::models::Manifest],
+) -> anyhow::Result<(Vec<crate::models::Manifest>, Vec<std::path::PathBuf>)> {
+    let mut owned_paths = owned_paths(root, installed).await?;
+
+    let mut hashes = HashCache::default();
+    let mut adopted = Vec::new();
+    for manifest in candidates {
+        if installed.iter().any(|m| m.id == manifest.id)
+            || adopted
+                .iter()
+                .any(|m: &crate::models::Manifest| m.id == manifest.id)
+        {
+            continue;
+        }
+        let Some(files) = match_manifest(root, manifest, &mut hashes).await? else {
+            continue;
+        };
+        log::info!("Adopting {}", manifest.id);
+        let receipt = Receipt {
+            id: manifest.id.clone(),
+            version: manifest.as_version(),
+            files,
+        };
+        let receipt_path = root.join(Receipt::relative_path(&manifest.id));
+        if let Some(parent) = receipt_path.parent() {
+            fs_err::tokio::create_dir_all(parent).await?;
+        }
+        fs_err::tokio::write(&receipt_path, serde_json::to_vec_pretty(&receipt)?).await?;
+        owned_paths.extend(receipt.files.iter().map(|f| f.path.as_relative_path()));
+        adopted.push(manifest.clone());
+    }
+
+    let mut unmatched = Vec::new();
+    for file in list_root_type_files(root).await? {
+        if !owned_paths.iter().any(|owned| file.starts_with(owned)) {
+            unmatched.push(file);
+        }
+    }
+
+    Ok((adopted, unmatched))
+}
+
+/// `manifest`のリソースが全てインストール先に存在するかを調べ、存在すればレシートのファイル一覧を返す。
+///
+/// 誤検出を防ぐため、SHA256ハッシュ値が指定されたリソースが少なくとも1つ一致する必要があります。
+async fn match_manifest(
+    root: &std::path::Path,
+    manifest: &crate::models::Manifest,
+    hashes: &mut HashCache,
+) -> anyhow::Result<Option<Vec<ReceiptFile>>> {
+    let mut files = Vec::new();
+    let mut verified = false;
+    for resource in &manifest.resources {
+        let abs_path = resource.destination.to_absolute_path(root);
+        let found = files_with_destinations(&abs_path, &resource.destination).await?;
+        if found.is_empty() {
+            return Ok(None);
+        }
+        if resource.destination.is_directory() != abs_path.is_dir() {
+            return Ok(None);
+        }
+        for (file, path) in found {
+            let (size, sha256) = hashes.get(&file).await?;
+            if let Some(expected) = resource.sha256.as_deref() {
+                if !expected.eq_ignore_ascii_case(&sha256) {
+                    return Ok(None);
+                }
+                verified = true;
+            }
+            files.push(ReceiptFile {
+                path,
+                scope: resource.scope.as_deref().cloned(),
+                size,
+                sha256,
+            });
+        }
+    }
+
+    Ok(verified.then_some(files))
+}
+
+#[derive(Debug, Default)]
+struct HashCache(std::collections::HashMap<std::path::PathBuf, (u64, String)>);
+
+impl HashCache {
+    async fn get(&mut self, path: &std::path::Path) -> anyhow::Result<(u64, String)> {
+        if let Some(hash) = self.0.get(path) {
+            return Ok(hash.clone());
+        }
+        let hash = hash_file(path).await?;
+        self.0.insert(path.to_path_buf(), hash.clone());
+        Ok(hash)
+    }
+}
@@ -0,0 +1,799 @@
+use futures::future::BoxFuture;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+/// アーカイブ内のファイル名から、書き出し先のパスへの対応。
+/// 同じファイルを複数の場所に書き出すこともあるため、書き出し先は複数持てる。
+pub type ExtractTargets = std::collections::HashMap<String, Vec<std::path::PathBuf>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+    SevenZ,
+}
+
+impl ArchiveFormat {
+    /// ファイルの先頭のバイト列から形式を判定する。
+    ///
+    /// tarはファイルの先頭にマジックナンバーを持たないため、`ustar`が257バイト目にあるかで判定します。
+    /// 圧縮されたtarは、圧縮形式のマジックナンバーのみで判定します。
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::TarXz)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::TarZst)
+        } else if header.starts_with(&[b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c]) {
+            Some(Self::SevenZ)
+        } else if header.get(257..262) == Some(b"ustar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// 判定に必要な先頭のバイト数。
+    const HEADER_LEN: usize = 262;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveError {
+    #[error("#archive_unsafe_path[{}]", serde_json::to_string(.0).unwrap())]
+    UnsafePath(String),
+
+    #[error("#archive_too_many_entries[{0},{1}]")]
+    TooManyEntries(usize, usize),
+
+    #[error("#archive_too_large[{0},{1}]")]
+    TooLarge(u64, u64),
+
+    #[error("#archive_compression_ratio_too_high[{0},{1}]")]
+    CompressionRatioTooHigh(u64, u64),
+}
+
+/// 展開の上限。zip爆弾などへの対策。
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractLimits {
+    /// 展開後の合計サイズの上限。
+    pub max_total_size: u64,
+    /// ファイル数の上限。
+    pub max_entries: usize,
+    /// 展開後の合計サイズと、アーカイブのサイズの比の上限。
+    pub max_compression_ratio: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: 4 * 1024 * 1024 * 1024,
+            max_entries: 100_000,
+            max_compression_ratio: 100,
+        }
+    }
+}
+
+/// 圧縮率を調べ始める展開後のサイズ。
+/// 小さいファイルは圧縮率が極端に高くなりやすく、また害も無いので調べない。
+const RATIO_CHECK_MIN_SIZE: u64 = 16 * 1024 * 1024;
+
+/// 展開したサイズを数え、上限を超えていないかを調べる。
+#[derive(Debug)]
+struct SizeBudget {
+    limits: ExtractLimits,
+    archive_size: u64,
+    written: std::sync::atomic::AtomicU64,
+}
+
+impl SizeBudget {
+    fn new(limits: ExtractLimits, archive_size: u64) -> Self {
+        Self {
+            limits,
+            archive_size,
+            written: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+
+    fn check_entries(&self, count: usize) -> Result<(), ArchiveError> {
+        if count > self.limits.max_entries {
+            return Err(ArchiveError::TooManyEntries(count, self.limits.max_entries));
+        }
+        Ok(())
+    }
+
+    fn add(&self, bytes: u64) -> Result<(), ArchiveError> {
+        self.written
+            .fetch_add(bytes, std::sync::atomic::Ordering::Relaxed);
+        self.check()
+    }
+
+    fn check(&self) -> Result<(), ArchiveError> {
+        let written = self.written.load(std::sync::atomic::Ordering::Relaxed);
+        if written > self.limits.max_total_size {
+            return Err(ArchiveError::TooLarge(written, self.limits.max_total_size));
+        }
+        if written > RATIO_CHECK_MIN_SIZE {
+            let ratio = written / self.archive_size.max(1);
+            if ratio > self.limits.max_compression_ratio {
+                return Err(ArchiveError::CompressionRatioTooHigh(
+                    ratio,
+                    self.limits.max_compression_ratio,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 書き込んだサイズを`SizeBudget`に数えさせる`Write`。
+struct LimitedWriter<'a, W> {
+    inner: W,
+    budget: &'a SizeBudget,
+}
+
+impl<W: std::io::Write> std::io::Write for LimitedWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.budget
+            .add(buf.len() as u64)
+            .map_err(std::io::Error::other)?;
+        self.inner.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// アーカイブ内のパスを、`/`区切りの書き出し先からの相対パスにする。
+///
+/// `..`や絶対パスなど、書き出し先の外を指すパスはエラーにします。
+pub fn sanitize_entry_path(name: &str) -> Result<String, ArchiveError> {
+    if name.starts_with('/') {
+        return Err(ArchiveError::UnsafePath(name.to_owned()));
+    }
+    let mut path = Vec::new();
+    for component in name.split('/') {
+        match component {
+            "" | "." => continue,
+            // NOTE: Windowsでは`C:`のようなドライブ指定や`:`を含むパスも外を指しうる
+            ".." => return Err(ArchiveError::UnsafePath(name.to_owned())),
+            _ if component.contains(':') => return Err(ArchiveError::UnsafePath(name.to_owned())),
+            _ => path.push(component),
+        }
+    }
+    Ok(path.join("/"))
+}
+
+/// アーカイブからファイルを取り出すもの。形式ごとに実装する。
+pub trait Extractor: Send {
+    /// アーカイブ内のファイルの一覧。パスの区切りは`/`で、ディレクトリは含まない。
+    fn list(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<String>>>;
+
+    /// `targets`に含まれるファイルを取り出す。
+    fn extract<'a>(&'a mut self, targets: &'a ExtractTargets) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// アーカイブを開く際の設定。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenOptions {
+    /// zipのファイル名の文字コード。UTF-8フラグが付いていないファイル名にのみ使う。
+    pub filename_encoding: Option<&'static encoding_rs::Encoding>,
+    pub limits: ExtractLimits,
+}
+
+impl OpenOptions {
+    pub fn for_bundle(bundle: &crate::models::Bundle) -> anyhow::Result<Self> {
+        let filename_encoding = bundle
+            .encoding()
+            .map(|label| {
+                encoding_rs::Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "#unknown_encoding[{}]",
+                        serde_json::to_string(label).unwrap()
+                    )
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            filename_encoding,
+            ..Default::default()
+        })
+    }
+}
+
+/// `path`のアーカイブの形式を判定し、対応する`Extractor`を返す。
+pub async fn open(
+    path: &std::path::Path,
+    options: &OpenOptions,
+) -> anyhow::Result<Box<dyn Extractor>> {
+    let format = detect_format(path).await?;
+    log::debug!("Detected archive format of {}: {format}", path.display());
+    let budget = std::sync::Arc::new(SizeBudget::new(
+        options.limits,
+        fs_err::tokio::metadata(path).await?.len(),
+    ));
+    let path = path.to_path_buf();
+    Ok(match format {
+        ArchiveFormat::Zip => Box::new(ZipExtractor {
+            path,
+            filename_encoding: options.filename_encoding,
+            budget,
+        }),
+        ArchiveFormat::Tar => Box::new(TarExtractor {
+            path,
+            compression: TarCompression::None,
+            budget,
+            temporary: false,
+        }),
+        ArchiveFormat::TarGz => Box::new(TarExtractor {
+            path,
+            compression: TarCompression::Gzip,
+            budget,
+            temporary: false,
+        }),
+        ArchiveFormat::TarZst => Box::new(TarExtractor {
+            path,
+            compression: TarCompression::Zstd,
+            budget,
+            temporary: false,
+        }),
+        ArchiveFormat::TarXz => {
+            // NOTE: lzma-rsはストリームで読めないので、一度tarに展開しておく。
+            //       元のファイルが`*.tar`の場合もあるので、重ならない名前にする
+            let tar_path = path.with_extension(format!("{}.xz-decoded.tar", uuid::Uuid::now_v7()));
+            let decompressed = {
+                let tar_path = tar_path.clone();
+                let budget = SizeBudget::new(budget.limits, budget.archive_size);
+                tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+                    let mut reader = std::io::BufReader::new(fs_err::File::open(&path)?);
+                    let mut writer = std::io::BufWriter::new(LimitedWriter {
+                        inner: fs_err::File::create(&tar_path)?,
+                        budget: &budget,
+                    });
+                    if let Err(e) = lzma_rs::xz_decompress(&mut reader, &mut writer) {
+                        budget.check()?;
+                        anyhow::bail!("Failed to decompress xz: {e}");
+                    }
+                    Ok(())
+                })
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result)
+            };
+            if let Err(e) = decompressed {
+                if tar_path.exists() {
+                    fs_err::tokio::remove_file(&tar_path).await?;
+                }
+                return Err(e);
+            }
+            Box::new(TarExtractor {
+                path: tar_path,
+                compression: TarCompression::None,
+                budget,
+                temporary: true,
+            })
+        }
+        ArchiveFormat::SevenZ => Box::new(SevenZExtractor { path, budget }),
+    })
+}
+
+async fn detect_format(path: &std::path::Path) -> anyhow::Result<ArchiveFormat> {
+    use tokio::io::AsyncReadExt;
+
+    let mut file = fs_err::tokio::File::open(path).await?;
+    let mut header = Vec::with_capacity(ArchiveFormat::HEADER_LEN);
+    (&mut file)
+        .take(ArchiveFormat::HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .await?;
+    ArchiveFormat::detect(&header).ok_or_else(|| {
+        anyhow::anyhow!(
+            "#unsupported_archive_format[{}]",
+            serde_json::to_string(&path.to_string_lossy()).unwrap()
+        )
+    })
+}
+
+/// `reader`の内容を`dest`に書き出す。書き出したサイズは`budget`に数える。
+async fn copy_limited<R: futures::AsyncRead + Unpin>(
+    reader: &mut R,
+    dest: &std::path::Path,
+    budget: &SizeBudget,
+) -> anyhow::Result<()> {
+    use futures::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+
+    let mut writer = fs_err::tokio::File::create(dest).await?;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        budget.add(read as u64)?;
+        writer.write_all(&buf[..read]).await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+fn copy_limited_blocking(
+    reader: &mut dyn std::io::Read,
+    dest: &std::path::Path,
+    budget: &SizeBudget,
+) -> anyhow::Result<()> {
+    let mut writer = LimitedWriter {
+        inner: fs_err::File::create(dest)?,
+        budget,
+    };
+    if let Err(e) = std::io::copy(reader, &mut writer) {
+        budget.check()?;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// `dests`の1つ目に書き出されたファイルを、残りの書き出し先にコピーする。
+async fn copy_to_rest(dests: &[std::path::PathBuf]) -> anyhow::Result<()> {
+    for dest in &dests[1..] {
+        create_parent_dir(dest).await?;
+        fs_err::tokio::copy(&dests[0], dest).await?;
+    }
+    Ok(())
+}
+
+async fn create_parent_dir(path: &std::path::Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs_err::tokio::create_dir_all(parent).await?;
+    }
+    Ok(())
+}
+
+fn create_parent_dir_blocking(path: &std::path::Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs_err::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+struct ZipExtractor {
+    path: std::path::PathBuf,
+    filename_encoding: Option<&'static encoding_rs::Encoding>,
+    budget: std::sync::Arc<SizeBudget>,
+}
+
+impl ZipExtractor {
+    async fn reader(
+        &self,
+    ) -> anyhow::Result<
+        async_zip::base::read::seek::ZipFileReader<
+            tokio_util::compat::Compat<tokio::io::BufReader<fs_err::tokio::File>>,
+        >,
+    > {
+        Ok(async_zip::tokio::read::seek::ZipFileReader::new(
+            tokio::io::BufReader::new(fs_err::tokio::File::open(&self.path).await?).compat(),
+        )
+        .await?)
+    }
+}
+
+impl Extractor for ZipExtractor {
+    fn list(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<String>>> {
+        Box::pin(async move {
+            let zip = self.reader().await?;
+            let names = zip
+                .file()
+                .entries()
+                .iter()
+                .map(|e| decode_zip_filename(e.filename(), self.filename_encoding))
+                .filter(|name| !name.ends_with('/'))
+                .collect::<Vec<_>>();
+            self.budget.check_entries(names.len())?;
+            Ok(names)
+        })
+    }
+
+    fn extract<'a>(&'a mut self, targets: &'a ExtractTargets) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut zip = self.reader().await?;
+            let entries = zip
+                .file()
+                .entries()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, e)| {
+                    let dests =
+                        targets.get(&decode_zip_filename(e.filename(), self.filename_encoding))?;
+                    Some((i, dests))
+                })
+                .collect::<Vec<_>>();
+            for (index, dests) in entries {
+                create_parent_dir(&dests[0]).await?;
+                let mut reader = zip.reader_without_entry(index).await?;
+                copy_limited(&mut reader, &dests[0], &self.budget).await?;
+                copy_to_rest(dests).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// zipのファイル名を文字列にする。
+///
+/// UTF-8フラグが付いている場合はそのまま使います。
+/// 付いていない場合は`encoding`で、指定がなければUTF-8、Shift_JIS（CP932）の順に試して読みます。
+/// Windowsで作られたzipは日本語のファイル名をCP932で持っていることが多いため。
+pub fn decode_zip_filename(
+    filename: &async_zip::ZipString,
+    encoding: Option<&'static encoding_rs::Encoding>,
+) -> String {
+    let name = match filename.as_str() {
+        Ok(name) => name.to_owned(),
+        Err(_) => {
+            let raw = filename.as_bytes();
+            match encoding {
+                Some(encoding) => encoding.decode_without_bom_handling(raw).0.into_owned(),
+                None => match std::str::from_utf8(raw) {
+                    Ok(name) => name.to_owned(),
+                    Err(_) => encoding_rs::SHIFT_JIS
+                        .decode_without_bom_handling(raw)
+                        .0
+                        .into_owned(),
+                },
+            }
+        }
+    };
+    // NOTE: CP932の2バイト目に`\`が来ることがあるので、置き換えるのはデコードした後にする
+    name.replace('\\', "/")
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TarCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+struct TarExtractor {
+    path: std::path::PathBuf,
+    compression: TarCompression,
+    budget: std::sync::Arc<SizeBudget>,
+    /// `path`が展開のために作った一時ファイルかどうか。`true`の場合は使い終わったら削除する。
+    temporary: bool,
+}
+
+impl Drop for TarExtractor {
+    fn drop(&mut self) {
+        if self.temporary {
+            if let Err(e) = fs_err::remove_file(&self.path) {
+                log::warn!("Failed to remove decompressed tar: {e}");
+            }
+        }
+    }
+}
+
+impl TarExtractor {
+    fn archive_blocking(
+        path: &std::path::Path,
+        compression: TarCompression,
+    ) -> anyhow::Result<tar::Archive<Box<dyn std::io::Read>>> {
+        let file = std::io::BufReader::new(fs_err::File::open(path)?);
+        let reader: Box<dyn std::io::Read> = match compression {
+            TarCompression::None => Box::new(file),
+            TarCompression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+            TarCompression::Zstd => Box::new(ruzstd::decoding::StreamingDecoder::new(file)?),
+        };
+        Ok(tar::Archive::new(reader))
+    }
+
+    fn entry_name(entry: &tar::Entry<'_, Box<dyn std::io::Read>>) -> anyhow::Result<String> {
+        let path = entry.path()?;
+        Ok(path
+            .to_string_lossy()
+            .replace('\\', "/")
+            .trim_start_matches("./")
+            .to_owned())
+    }
+}
+
+impl Extractor for TarExtractor {
+    fn list(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<String>>> {
+        let path = self.path.clone();
+        let compression = self.compression;
+        let budget = self.budget.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let mut archive = Self::archive_blocking(&path, compression)?;
+                let mut names = vec![];
+                for entry in archive.entries()? {
+                    let entry = entry?;
+                    if entry.header().entry_type().is_file() {
+                        names.push(Self::entry_name(&entry)?);
+                        budget.check_entries(names.len())?;
+                    }
+                }
+                Ok(names)
+            })
+            .await?
+        })
+    }
+
+    fn extract<'a>(&'a mut self, targets: &'a ExtractTargets) -> BoxFuture<'a, anyhow::Result<()>> {
+        let path = self.path.clone();
+        let compression = self.compression;
+        let budget = self.budget.clone();
+        let targets = targets.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let mut archive = Self::archive_blocking(&path, compression)?;
+                for entry in archive.entries()? {
+                    let mut entry = entry?;
+                    if !entry.header().entry_type().is_file() {
+                        continue;
+                    }
+                    let Some(dests) = targets.get(&Self::entry_name(&entry)?) else {
+                        continue;
+                    };
+                    for dest in dests {
+                        create_parent_dir_blocking(dest)?;
+                    }
+                    copy_limited_blocking(&mut entry, &dests[0], &budget)?;
+                    for dest in &dests[1..] {
+                        fs_err::copy(&dests[0], dest)?;
+                    }
+                }
+                Ok(())
+            })
+            .await?
+        })
+    }
+}
+
+struct SevenZExtractor {
+    path: std::path::PathBuf,
+    budget: std::sync::Arc<SizeBudget>,
+}
+
+impl SevenZExtractor {
+    fn reader_blocking(
+        path: &std::path::Path,
+    ) -> anyhow::Result<sevenz_rust::SevenZReader<std::fs::File>> {
+        Ok(sevenz_rust::SevenZReader::open(
+            path,
+            sevenz_rust::Password::empty(),
+        )?)
+    }
+
+    fn entry_name(entry: &sevenz_rust::SevenZArchiveEntry) -> String {
+        entry.name().replace('\\', "/")
+    }
+}
+
+impl Extractor for SevenZExtractor {
+    fn list(&mut self) -> BoxFuture<'_, anyhow::Result<Vec<String>>> {
+        let path = self.path.clone();
+        let budget = self.budget.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let reader = Self::reader_blocking(&path)?;
+                let names = reader
+                    .archive()
+                    .files
+                    .iter()
+                    .filter(|entry| !entry.is_directory())
+                    .map(Self::entry_name)
+                    .collect::<Vec<_>>();
+                budget.check_entries(names.len())?;
+                Ok(names)
+            })
+            .await?
+        })
+    }
+
+    fn extract<'a>(&'a mut self, targets: &'a ExtractTargets) -> BoxFuture<'a, anyhow::Result<()>> {
+        let path = self.path.clone();
+        let budget = self.budget.clone();
+        let targets = targets.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let mut reader = Self::reader_blocking(&path)?;
+                let result = reader.for_each_entries(|entry, entry_reader| {
+                    let dests = if entry.is_directory() {
+                        None
+                    } else {
+                        targets.get(&Self::entry_name(entry))
+                    };
+                    let Some(dests) = dests else {
+                        // NOTE: 同じフォルダ内の後続のエントリを読むために、不要なエントリも読み切る必要がある
+                        std::io::copy(entry_reader, &mut std::io::sink())?;
+                        return Ok(true);
+                    };
+                    for dest in dests {
+                        create_parent_dir_blocking(dest)?;
+                    }
+                    let mut writer = LimitedWriter {
+                        inner: fs_err::File::create(&dests[0])?,
+                        budget: &budget,
+                    };
+                    std::io::copy(entry_reader, &mut writer)?;
+                    for dest in &dests[1..] {
+                        fs_err::copy(&dests[0], dest)?;
+                    }
+                    Ok(true)
+                });
+                if let Err(e) = result {
+                    budget.check()?;
+                    return Err(e.into());
+                }
+                Ok(())
+            })
+            .await?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::zip(b"PK\x03\x04".to_vec(), Some(ArchiveFormat::Zip))]
+    #[case::empty_zip(b"PK\x05\x06".to_vec(), Some(ArchiveFormat::Zip))]
+    #[case::gzip(vec![0x1f, 0x8b, 0x08], Some(ArchiveFormat::TarGz))]
+    #[case::xz(vec![0xfd, b'7', b'z', b'X', b'Z', 0x00], Some(ArchiveFormat::TarXz))]
+    #[case::zstd(vec![0x28, 0xb5, 0x2f, 0xfd], Some(ArchiveFormat::TarZst))]
+    #[case::seven_z(vec![b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c], Some(ArchiveFormat::SevenZ))]
+    #[case::tar({
+        let mut header = vec![0; 512];
+        header[257..262].copy_from_slice(b"ustar");
+        header
+    }, Some(ArchiveFormat::Tar))]
+    #[case::unknown(b"hello".to_vec(), None)]
+    fn test_detect(#[case] header: Vec<u8>, #[case] expected: Option<ArchiveFormat>) {
+        assert_eq!(ArchiveFormat::detect(&header), expected);
+    }
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[rstest::rstest]
+    #[case::cp932("cp932.zip", None)]
+    #[case::cp932_with_encoding("cp932.zip", Some(encoding_rs::SHIFT_JIS))]
+    #[case::utf8("utf8.zip", None)]
+    #[case::utf8_ignores_encoding("utf8.zip", Some(encoding_rs::SHIFT_JIS))]
+    #[tokio::test]
+    async fn test_zip_filename_encoding(
+        #[case] name: &str,
+        #[case] filename_encoding: Option<&'static encoding_rs::Encoding>,
+    ) {
+        let mut extractor = open(
+            &fixture(name),
+            &OpenOptions {
+                filename_encoding,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let mut names = extractor.list().await.unwrap();
+        names.sort();
+        assert_eq!(
+            names,
+            ["スクリプト/readme.txt", "スクリプト/表示.anm", "ソ.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_cp932_zip() {
+        let out_dir = std::env::temp_dir().join(uuid::Uuid::now_v7().to_string());
+        let dest = out_dir.join("表示.anm");
+        let mut extractor = open(&fixture("cp932.zip"), &OpenOptions::default())
+            .await
+            .unwrap();
+        extractor
+            .extract(&ExtractTargets::from([(
+                "スクリプト/表示.anm".to_owned(),
+                vec![dest.clone()],
+            )]))
+            .await
+            .unwrap();
+        let content = fs_err::tokio::read(&dest).await.unwrap();
+        fs_err::tokio::remove_dir_all(&out_dir).await.unwrap();
+        assert_eq!(content, *encoding_rs::SHIFT_JIS.encode("--表示\n").0);
+    }
+
+    #[tokio::test]
+    async fn test_xz_named_tar() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let out_dir = temp_dir.path();
+        let mut builder = tar::Builder::new(vec![]);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "a.txt", &b"hello"[..])
+            .unwrap();
+        let mut xz = vec![];
+        lzma_rs::xz_compress(&mut &builder.into_inner().unwrap()[..], &mut xz).unwrap();
+        let path = out_dir.join("bundle.tar");
+        fs_err::tokio::write(&path, &xz).await.unwrap();
+
+        let mut extractor = open(&path, &OpenOptions::default()).await.unwrap();
+        assert_eq!(extractor.list().await.unwrap(), ["a.txt"]);
+        drop(extractor);
+
+        assert_eq!(fs_err::tokio::read(&path).await.unwrap(), xz);
+        assert_eq!(std::fs::read_dir(out_dir).unwrap().count(), 1);
+    }
+
+    #[rstest::rstest]
+    #[case::plain("a/b.txt", Some("a/b.txt"))]
+    #[case::current_dir("./a/./b.txt", Some("a/b.txt"))]
+    #[case::empty_segments("a//b.txt", Some("a/b.txt"))]
+    #[case::parent("../b.txt", None)]
+    #[case::nested_parent("a/../../b.txt", None)]
+    #[case::absolute("/etc/passwd", None)]
+    #[case::drive("C:/Windows/b.txt", None)]
+    fn test_sanitize_entry_path(#[case] name: &str, #[case] expected: Option<&str>) {
+        assert_eq!(sanitize_entry_path(name).ok().as_deref(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let out_dir = std::env::temp_dir().join(uuid::Uuid::now_v7().to_string());
+        let open_with = |limits: ExtractLimits| async move {
+            open(
+                &fixture("bomb.zip"),
+                &OpenOptions {
+                    limits,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap()
+        };
+        let targets =
+            ExtractTargets::from([("zeros.bin".to_owned(), vec![out_dir.join("zeros.bin")])]);
+
+        let mut extractor = open_with(ExtractLimits {
+            max_entries: 0,
+            ..Default::default()
+        })
+        .await;
+        let error = extractor.list().await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ArchiveError::TooManyEntries(1, 0))
+        ));
+
+        let mut extractor = open_with(ExtractLimits {
+            max_total_size: 1024,
+            ..Default::default()
+        })
+        .await;
+        let error = extractor.extract(&targets).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ArchiveError::TooLarge(_, 1024))
+        ));
+
+        let mut extractor = open_with(ExtractLimits::default()).await;
+        let error = extractor.extract(&targets).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ArchiveError::CompressionRatioTooHigh(_, 100))
+        ));
+
+        fs_err::tokio::remove_dir_all(&out_dir).await.unwrap();
+    }
+}
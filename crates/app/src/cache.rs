@@ -0,0 +1,217 @@
+static INDEX_FILE: &str = "index.json";
+
+/// キャッシュのキー。SHA256ハッシュ値が分かっている場合はそれを、分からない場合はURLを使う。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheKey {
+    Sha256(String),
+    Url(url::Url),
+}
+
+impl std::fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheKey::Sha256(sha256) => write!(f, "sha256:{}", sha256.to_ascii_lowercase()),
+            CacheKey::Url(url) => write!(f, "url:{url}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct CacheEntry {
+    file: String,
+    size: u64,
+    last_used: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct CacheIndex {
+    entries: std::collections::BTreeMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CacheInfo {
+    pub num_entries: usize,
+    pub total_size: u64,
+    pub max_size: u64,
+}
+
+/// プロファイル間で共有する、ダウンロードしたファイルのキャッシュ。
+///
+/// 最後に使われた日時を記録し、`max_size`を超えた場合は古いものから削除します。
+#[derive(Debug)]
+pub struct DownloadCache {
+    dir: std::path::PathBuf,
+    index: tokio::sync::Mutex<Option<CacheIndex>>,
+}
+
+impl DownloadCache {
+    pub fn new(dir: std::path::PathBuf) -> Self {
+        Self {
+            dir,
+            index: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// キャッシュされたファイルを`dest`にコピーする。キャッシュされていない場合は`false`を返す。
+    pub async fn get(&self, key: &CacheKey, dest: &std::path::Path) -> anyhow::Result<bool> {
+        let key = key.to_string();
+        let file = {
+            let mut guard = self.index.lock().await;
+            let index = self.load_index(&mut guard).await?;
+            let Some(entry) = index.entries.get(&key) else {
+                return Ok(false);
+            };
+            let file = self.dir.join(&entry.file);
+            if !file.exists() {
+                index.entries.remove(&key);
+                self.save_index(index).await?;
+                return Ok(false);
+            }
+            file
+        };
+        // NOTE: コピーの間はロックを外しておく。その間に削除された場合はキャッシュされていないものとする
+        match fs_err::tokio::copy(&file, dest).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        let mut guard = self.index.lock().await;
+        let index = self.load_index(&mut guard).await?;
+        if let Some(entry) = index.entries.get_mut(&key) {
+            entry.last_used = chrono::Utc::now();
+            self.save_index(index).await?;
+        }
+        Ok(true)
+    }
+
+    /// `key`のキャッシュされたファイルの大きさ。キャッシュされていない場合は`None`を返す。
+    pub async fn size(&self, key: &CacheKey) -> anyhow::Result<Option<u64>> {
+        let mut guard = self.index.lock().await;
+        let index = self.load_index(&mut guard).await?;
+        Ok(index.entries.get(&key.to_string()).map(|entry| entry.size))
+    }
+
+    /// `key`のキャッシュに記録されたETag。
+    pub async fn etag(&self, key: &CacheKey) -> anyhow::Result<Option<String>> {
+        let mut guard = self.index.lock().await;
+        let index = self.load_index(&mut guard).await?;
+        Ok(index
+            .entries
+            .get(&key.to_string())
+            .and_then(|entry| entry.etag.clone()))
+    }
+
+    /// `file`をキャッシュに追加し、合計サイズが`max_size`を超えた分を削除する。
+    pub async fn insert(
+        &self,
+        key: &CacheKey,
+        file: &std::path::Path,
+        etag: Option<String>,
+        max_size: u64,
+    ) -> anyhow::Result<()> {
+        let key = key.to_string();
+        let file_name = {
+            use sha2::Digest;
+            format!("{:x}", sha2::Sha256::digest(key.as_bytes()))
+        };
+        let size = fs_err::tokio::metadata(file).await?.len();
+        if size > max_size {
+            return Ok(());
+        }
+        // NOTE: コピーはロックを取る前に済ませておく
+        fs_err::tokio::create_dir_all(&self.dir).await?;
+        let tmp_path = self
+            .dir
+            .join(format!("{file_name}.{}.tmp", uuid::Uuid::now_v7()));
+        fs_err::tokio::copy(file, &tmp_path).await?;
+
+        let mut guard = self.index.lock().await;
+        let index = self.load_index(&mut guard).await?;
+        fs_err::tokio::rename(&tmp_path, self.dir.join(&file_name)).await?;
+        index.entries.insert(
+            key,
+            CacheEntry {
+                file: file_name,
+                size,
+                last_used: chrono::Utc::now(),
+                etag,
+            },
+        );
+        self.evict(index, max_size).await?;
+        self.save_index(index).await
+    }
+
+    pub async fn info(&self, max_size: u64) -> anyhow::Result<CacheInfo> {
+        let mut guard = self.index.lock().await;
+        let index = self.load_index(&mut guard).await?;
+        Ok(CacheInfo {
+            num_entries: index.entries.len(),
+            total_size: index.entries.values().map(|entry| entry.size).sum(),
+            max_size,
+        })
+    }
+
+    pub async fn clear(&self) -> anyhow::Result<()> {
+        let mut guard = self.index.lock().await;
+        if self.dir.exists() {
+            fs_err::tokio::remove_dir_all(&self.dir).await?;
+        }
+        *guard = Some(CacheIndex::default());
+        Ok(())
+    }
+
+    async fn evict(&self, index: &mut CacheIndex, max_size: u64) -> anyhow::Result<()> {
+        let mut total_size: u64 = index.entries.values().map(|entry| entry.size).sum();
+        while total_size > max_size {
+            let Some(key) = index
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            let entry = index.entries.remove(&key).unwrap();
+            log::info!("Evicting cache entry: {key}");
+            let file = self.dir.join(&entry.file);
+            if file.exists() {
+                fs_err::tokio::remove_file(&file).await?;
+            }
+            total_size -= entry.size;
+        }
+        Ok(())
+    }
+
+    async fn load_index<'a>(
+        &self,
+        index: &'a mut Option<CacheIndex>,
+    ) -> anyhow::Result<&'a mut CacheIndex> {
+        if index.is_none() {
+            let path = self.dir.join(INDEX_FILE);
+            let loaded = if path.exists() {
+                match serde_json::from_slice(&fs_err::tokio::read(&path).await?) {
+                    Ok(loaded) => loaded,
+                    Err(e) => {
+                        log::warn!("Failed to load cache index, starting over: {e}");
+                        CacheIndex::default()
+                    }
+                }
+            } else {
+                CacheIndex::default()
+            };
+            *index = Some(loaded);
+        }
+        Ok(index.as_mut().unwrap())
+    }
+
+    async fn save_index(&self, index: &CacheIndex) -> anyhow::Result<()> {
+        fs_err::tokio::create_dir_all(&self.dir).await?;
+        let path = self.dir.join(INDEX_FILE);
+        let tmp_path = self.dir.join(format!("{INDEX_FILE}.tmp"));
+        fs_err::tokio::write(&tmp_path, serde_json::to_vec_pretty(index)?).await?;
+        fs_err::tokio::rename(&tmp_path, &path).await?;
+        Ok(())
+    }
+}
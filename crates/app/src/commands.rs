@@ -1,5 +1,6 @@
 use cached::proc_macro::cached;
 use std::time::Duration;
+use tauri::Manager;
 
 use crate::{
     anyhow_to_string, installer::DATA_DIR, models, path_match::matches_path, store::open_store,
@@ -189,7 +190,7 @@ pub async fn fetch_manifest(
             let manifest = store
                 .contents
                 .get(&id.parse()?)
-                .cloned()
+                .map(|installed| installed.manifest.clone())
                 .ok_or_else(|| anyhow::anyhow!("#not_found"))?;
             return Ok(manifest);
         } else {
@@ -313,6 +314,21 @@ pub async fn remove_registry(app: &tauri::AppHandle, registry: uuid::Uuid) -> an
     Ok(())
 }
 
+pub async fn get_settings(app: &tauri::AppHandle) -> anyhow::Result<crate::store::Settings> {
+    let index_store = crate::utils::open_index_store(app).await?;
+    Ok(index_store.settings.clone())
+}
+
+pub async fn set_settings(
+    app: &tauri::AppHandle,
+    settings: crate::store::Settings,
+) -> anyhow::Result<()> {
+    let mut index_store = crate::utils::open_index_store(app).await?;
+    index_store.settings = settings;
+    index_store.save().await?;
+    Ok(())
+}
+
 pub async fn get_profile_store(
     app: &tauri::AppHandle,
     profile_id: uuid::Uuid,
@@ -335,6 +351,10 @@ pub async fn plan_installation(
     app: &tauri::AppHandle,
     profile_id: uuid::Uuid,
     desired: Vec<models::Manifest>,
+    resolutions: std::collections::HashMap<
+        std::path::PathBuf,
+        crate::installer::UntrackedFileResolution,
+    >,
 ) -> anyhow::Result<crate::installer::InstallPlan> {
     let index_store = crate::utils::open_index_store(app).await?;
     if !index_store.profiles.contains_key(&profile_id) {
@@ -343,8 +363,26 @@ pub async fn plan_installation(
     let profile_path = index_store.profiles.get(&profile_id).unwrap().path.clone();
     drop(index_store);
     let store = get_profile_store(app, profile_id).await?;
-    let existing: Vec<models::Manifest> = store.contents.values().cloned().collect();
-    let plan = crate::installer::InstallPlan::plan(&profile_path, &existing, &desired, &[])?;
+    let existing: Vec<models::Manifest> = store
+        .contents
+        .values()
+        .map(|installed| installed.manifest.clone())
+        .collect();
+    let listings = crate::installer::cached_bundle_listings(
+        &profile_path,
+        &desired,
+        app.state::<crate::cache::DownloadCache>().inner(),
+    )
+    .await?;
+    let plan = crate::installer::InstallPlan::plan(
+        &profile_path,
+        &existing,
+        &desired,
+        &[],
+        &resolutions,
+        &listings,
+    )
+    .await?;
     Ok(plan)
 }
 
@@ -354,14 +392,679 @@ pub async fn perform_installation(
     plan: crate::installer::InstallPlan,
     ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
 ) -> anyhow::Result<()> {
+    perform_plan(app, profile_id, plan, ch).await?;
+    record_generation(app, profile_id).await;
+    Ok(())
+}
+
+/// 今のプロファイルの状態を世代として記録する。インストール自体は終わっているので、失敗しても警告にとどめる。
+async fn record_generation(app: &tauri::AppHandle, profile_id: uuid::Uuid) {
+    let result: anyhow::Result<()> = async {
+        let profile_path = find_profile_path(app, profile_id).await?;
+        let store = get_profile_store(app, profile_id).await?;
+        let settings = crate::utils::open_index_store(app).await?.settings.clone();
+        let generation =
+            crate::generation::Generation::record(&profile_path, &store.contents).await?;
+        log::info!("Recorded generation {} of {profile_id}", generation.number);
+        if let Some(max_count) = settings.generation_max_count {
+            crate::generation::prune_generations(&profile_path, max_count).await?;
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(e) = result {
+        log::warn!("Failed to record generation: {e:?}");
+    }
+}
+
+async fn perform_plan(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    plan: crate::installer::InstallPlan,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> anyhow::Result<()> {
+    let index_store = crate::utils::open_index_store(app).await?;
+    if !index_store.profiles.contains_key(&profile_id) {
+        anyhow::bail!("#profile_not_found");
+    }
+    let profile_path = index_store.profiles.get(&profile_id).unwrap().path.clone();
+    let settings = index_store.settings.clone();
+    drop(index_store);
+
+    let registries = find_source_registries(
+        app,
+        plan.to_install
+            .iter()
+            .chain(plan.to_update.iter().map(|(_, new)| new)),
+    )
+    .await?;
+
+    let store = get_profile_store(app, profile_id).await?;
+    crate::transaction::Transaction::recover(&profile_path).await?;
+    plan.perform(
+        &profile_path,
+        &install_options(app, &settings),
+        ch,
+        &mut ProfileStoreUpdater { store, registries },
+    )
+    .await?;
+
+    let retention = crate::config_backup::BackupRetention {
+        max_count: settings.config_backup_max_count,
+        max_age_days: settings.config_backup_max_age_days,
+    };
+    if let Err(e) = crate::config_backup::prune_backups(&profile_path, &retention).await {
+        log::warn!("Failed to prune configuration backups: {e:?}");
+    }
+
+    Ok(())
+}
+
+pub async fn dry_run_installation(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    plan: crate::installer::InstallPlan,
+) -> anyhow::Result<Vec<crate::vfs::FileOperation>> {
+    let index_store = crate::utils::open_index_store(app).await?;
+    if !index_store.profiles.contains_key(&profile_id) {
+        anyhow::bail!("#profile_not_found");
+    }
+    let profile_path = index_store.profiles.get(&profile_id).unwrap().path.clone();
+    drop(index_store);
+
+    let manifests = plan
+        .to_install
+        .iter()
+        .chain(plan.to_update.iter().map(|(_, new)| new))
+        .chain(plan.to_repair.iter().map(|(manifest, _)| manifest))
+        .cloned()
+        .collect::<Vec<_>>();
+    let cache = app.state::<crate::cache::DownloadCache>();
+    let listings =
+        crate::installer::cached_bundle_listings(&profile_path, &manifests, cache.inner()).await?;
+    plan.dry_run(&profile_path, &listings, cache.inner()).await
+}
+
+fn install_options<'a>(
+    app: &'a tauri::AppHandle,
+    settings: &crate::store::Settings,
+) -> crate::installer::InstallOptions<'a> {
+    crate::installer::InstallOptions {
+        download_concurrency: settings.download_concurrency,
+        cache: Some(app.state::<crate::cache::DownloadCache>().inner()),
+        cache_max_size: settings.cache_max_size,
+    }
+}
+
+pub async fn get_cache_info(app: &tauri::AppHandle) -> anyhow::Result<crate::cache::CacheInfo> {
+    let settings = crate::utils::open_index_store(app).await?.settings.clone();
+    app.state::<crate::cache::DownloadCache>()
+        .info(settings.cache_max_size)
+        .await
+}
+
+pub async fn clear_cache(app: &tauri::AppHandle) -> anyhow::Result<()> {
+    app.state::<crate::cache::DownloadCache>().clear().await
+}
+
+struct ProfileStoreUpdater {
+    store: crate::store::LockedStore<crate::store::ProfileStore>,
+    registries: std::collections::HashMap<models::ManifestId, url::Url>,
+}
+
+impl crate::installer::InstallObserver for ProfileStoreUpdater {
+    async fn on_complete(
+        &mut self,
+        id: &models::ManifestId,
+        manifest: Option<&models::Manifest>,
+    ) -> anyhow::Result<()> {
+        match manifest {
+            Some(manifest) => {
+                self.store.contents.insert(
+                    id.clone(),
+                    crate::store::InstalledManifest {
+                        manifest: manifest.clone(),
+                        installed_at: chrono::Utc::now(),
+                        registry: self.registries.get(id).cloned(),
+                    },
+                );
+            }
+            None => {
+                self.store.contents.remove(id);
+            }
+        }
+        self.store.save().await
+    }
+}
+
+pub async fn scan_and_adopt(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> anyhow::Result<crate::adopt::AdoptResult> {
     let index_store = crate::utils::open_index_store(app).await?;
     if !index_store.profiles.contains_key(&profile_id) {
         anyhow::bail!("#profile_not_found");
     }
     let profile_path = index_store.profiles.get(&profile_id).unwrap().path.clone();
+    let registry_urls: Vec<url::Url> = index_store.registries.values().cloned().collect();
+    let mut manifest_urls: Vec<url::Url> = index_store.manifests.values().cloned().collect();
+    drop(index_store);
+
+    let mut registries = std::collections::HashMap::new();
+    for registry_url in registry_urls {
+        let registry = match fetch_registry_cached(registry_url.clone()).await {
+            Ok(registry) => registry,
+            Err(e) => {
+                log::warn!("Failed to fetch registry {registry_url}: {e}");
+                continue;
+            }
+        };
+        for content in registry.contents {
+            match url::Url::parse(&content.manifest_url) {
+                Ok(manifest_url) => {
+                    registries.insert(content.id, registry_url.clone());
+                    manifest_urls.push(manifest_url);
+                }
+                Err(e) => log::warn!("Invalid manifest URL {}: {e}", content.manifest_url),
+            }
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for manifest_url in manifest_urls {
+        match fetch_manifest_cached(app, manifest_url.clone()).await {
+            Ok(manifest) => candidates.push(manifest),
+            Err(e) => log::warn!("Failed to fetch manifest {manifest_url}: {e}"),
+        }
+    }
+
+    let mut store = get_profile_store(app, profile_id).await?;
+    let installed: Vec<models::Manifest> = store
+        .contents
+        .values()
+        .map(|installed| installed.manifest.clone())
+        .collect();
+    let (adopted, unmatched) =
+        crate::adopt::scan_and_adopt(&profile_path, &installed, &candidates).await?;
+
+    for manifest in &adopted {
+        store.contents.insert(
+            manifest.id.clone(),
+            crate::store::InstalledManifest {
+                manifest: manifest.clone(),
+                installed_at: chrono::Utc::now(),
+                registry: registries.get(&manifest.id).cloned(),
+            },
+        );
+    }
+    store.save().await?;
+
+    Ok(crate::adopt::AdoptResult {
+        adopted: adopted.into_iter().map(|m| m.id).collect(),
+        unmatched,
+    })
+}
+
+async fn open_profile_with_installed(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> anyhow::Result<(std::path::PathBuf, Vec<models::Manifest>)> {
+    let index_store = crate::utils::open_index_store(app).await?;
+    if !index_store.profiles.contains_key(&profile_id) {
+        anyhow::bail!("#profile_not_found");
+    }
+    let profile_path = index_store.profiles.get(&profile_id).unwrap().path.clone();
+    drop(index_store);
+    let store = get_profile_store(app, profile_id).await?;
+    let installed = store
+        .contents
+        .values()
+        .map(|installed| installed.manifest.clone())
+        .collect();
+    Ok((profile_path, installed))
+}
+
+pub async fn list_orphans(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> anyhow::Result<std::collections::BTreeMap<String, Vec<crate::orphan::OrphanFile>>> {
+    let (profile_path, installed) = open_profile_with_installed(app, profile_id).await?;
+    crate::orphan::list_orphans(&profile_path, &installed).await
+}
+
+pub async fn quarantine_orphans(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    paths: Vec<std::path::PathBuf>,
+) -> anyhow::Result<std::path::PathBuf> {
+    let (profile_path, installed) = open_profile_with_installed(app, profile_id).await?;
+    crate::orphan::quarantine_orphans(&profile_path, &installed, &paths).await
+}
+
+async fn find_profile_path(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> anyhow::Result<std::path::PathBuf> {
+    let index_store = crate::utils::open_index_store(app).await?;
+    let Some(profile) = index_store.profiles.get(&profile_id) else {
+        anyhow::bail!("#profile_not_found");
+    };
+    Ok(profile.path.clone())
+}
+
+pub async fn list_config_backups(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    manifest_id: Option<models::ManifestId>,
+) -> anyhow::Result<Vec<crate::config_backup::ConfigBackup>> {
+    let profile_path = find_profile_path(app, profile_id).await?;
+    crate::config_backup::list_backups(&profile_path, manifest_id.as_ref()).await
+}
+
+pub async fn preview_config_backup(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    manifest_id: models::ManifestId,
+    file_name: String,
+) -> anyhow::Result<Vec<crate::config_backup::ConfigBackupEntry>> {
+    let profile_path = find_profile_path(app, profile_id).await?;
+    crate::config_backup::preview_backup(&profile_path, &manifest_id, &file_name).await
+}
+
+pub async fn restore_config_backup(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    manifest_id: models::ManifestId,
+    file_name: String,
+    target_profile_id: Option<uuid::Uuid>,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let profile_path = find_profile_path(app, profile_id).await?;
+    let target_path = match target_profile_id {
+        Some(target_profile_id) => find_profile_path(app, target_profile_id).await?,
+        None => profile_path.clone(),
+    };
+    crate::transaction::Transaction::recover(&target_path).await?;
+    crate::config_backup::restore_backup(&profile_path, &manifest_id, &file_name, &target_path)
+        .await
+}
+
+pub async fn export_profile(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    dest: std::path::PathBuf,
+) -> anyhow::Result<()> {
+    let profile_path = find_profile_path(app, profile_id).await?;
+    let store = get_profile_store(app, profile_id).await?;
+    let environment = crate::environment::export_environment(&profile_path, &store, &dest).await?;
+    log::info!(
+        "Exported {} manifests of {profile_id} to {dest:?}",
+        environment.contents.len()
+    );
+    Ok(())
+}
+
+/// `archive`の環境を`path`に新しいプロファイルとして作り直す。
+/// `name`が指定されない場合は、エクスポート元のプロファイルの名前を使う。
+pub async fn import_profile(
+    app: &tauri::AppHandle,
+    archive: std::path::PathBuf,
+    name: Option<String>,
+    path: std::path::PathBuf,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> anyhow::Result<uuid::Uuid> {
+    let environment = crate::environment::read_environment(&archive).await?;
+    let desired = environment
+        .contents
+        .values()
+        .map(|pinned| pinned.manifest.clone())
+        .collect::<Vec<_>>();
+    // NOTE: インストールできない場合にプロファイルが残らないように、作る前に計画を立てる
+    let data_dir = path.join(DATA_DIR);
+    let had_data_dir = data_dir.exists();
+    let plan = match plan_exact(app, &path, &[], &desired, &Default::default()).await {
+        Ok(plan) => plan,
+        Err(e) => {
+            // 計画中に作られた作業用のディレクトリも残さない
+            if !had_data_dir && data_dir.exists() {
+                if let Err(remove_error) = fs_err::tokio::remove_dir_all(&data_dir).await {
+                    log::error!("Failed to remove {data_dir:?}: {remove_error:?}");
+                }
+            }
+            return Err(e);
+        }
+    };
+    let profile_id = initialize_profile(
+        app,
+        name.unwrap_or_else(|| environment.name.clone()),
+        path.clone(),
+        OnExist::Abort,
+    )
+    .await?;
+
+    let result = async {
+        perform_installation(app, profile_id, plan, ch.clone()).await?;
+
+        // NOTE: インデックスに無いレジストリからインストールされたものは、エクスポート元の記録を引き継ぐ
+        let mut store = get_profile_store(app, profile_id).await?;
+        let failed = desired
+            .iter()
+            .filter(|manifest| !store.contents.contains_key(&manifest.id))
+            .map(|manifest| manifest.id.to_string())
+            .collect::<Vec<_>>();
+        if !failed.is_empty() {
+            anyhow::bail!(
+                "#import_failed[{}]",
+                serde_json::to_string(&failed.join(", "))?
+            );
+        }
+        for (id, installed) in store.contents.iter_mut() {
+            if installed.registry.is_none() {
+                installed.registry = environment
+                    .contents
+                    .get(id)
+                    .and_then(|pinned| pinned.registry.clone());
+            }
+        }
+        store.save().await?;
+
+        crate::environment::restore_configurations(&archive, &path).await
+    }
+    .await;
+    match result {
+        Ok(restored) => {
+            log::info!(
+                "Imported {archive:?} into {path:?} ({} configuration files)",
+                restored.len()
+            );
+            Ok(profile_id)
+        }
+        Err(e) => {
+            if let Err(discard_error) = discard_profile(app, profile_id, ch).await {
+                log::error!("Failed to discard imported profile: {discard_error:?}");
+            }
+            Err(e)
+        }
+    }
+}
+
+/// インポートに失敗したプロファイルを、インストールしたものをアンインストールしてから削除する。
+async fn discard_profile(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> anyhow::Result<()> {
+    let store = get_profile_store(app, profile_id).await?;
+    let plan = crate::installer::InstallPlan {
+        to_uninstall: store
+            .contents
+            .values()
+            .map(|installed| installed.manifest.clone())
+            .collect(),
+        ..Default::default()
+    };
+    drop(store);
+    perform_plan(app, profile_id, plan, ch).await?;
+    remove_profile(app, profile_id).await
+}
+
+pub async fn generate_lockfile(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> anyhow::Result<std::path::PathBuf> {
+    let (profile_path, installed) = open_profile_with_installed(app, profile_id).await?;
+    let lockfile = crate::lockfile::Lockfile::generate(&profile_path, &installed).await?;
+    lockfile.save(&profile_path).await
+}
+
+/// ロックファイルに記録された状態になるように、プロファイルにインストールする。
+/// `lockfile`が指定されない場合は、プロファイルの`au2ec/lock.yml`を使う。
+pub async fn sync_from_lockfile(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    lockfile: Option<std::path::PathBuf>,
+    resolutions: std::collections::HashMap<
+        std::path::PathBuf,
+        crate::installer::UntrackedFileResolution,
+    >,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> anyhow::Result<crate::installer::InstallPlan> {
+    let (profile_path, installed) = open_profile_with_installed(app, profile_id).await?;
+    let lockfile_path =
+        lockfile.unwrap_or_else(|| profile_path.join(crate::lockfile::Lockfile::relative_path()));
+    let lockfile = crate::lockfile::Lockfile::load(&lockfile_path).await?;
+
+    let mut desired = Vec::new();
+    for (id, locked) in &lockfile.manifests {
+        // NOTE: 同じバージョンがインストールされていればそれを、無ければロックファイルに記録されたものを使う。
+        //       マニフェストが記録されていない古いロックファイルの場合のみ取得し直す
+        let manifest = match installed
+            .iter()
+            .find(|m| &m.id == id && m.version.to_string() == locked.version)
+            .or(locked.manifest.as_ref())
+        {
+            Some(manifest) => manifest.clone(),
+            None => {
+                let Some(manifest_url) = &locked.manifest_url else {
+                    anyhow::bail!(
+                        "#lockfile_manifest_url_missing[{}]",
+                        serde_json::to_string(id).unwrap()
+                    );
+                };
+                fetch_manifest(app, manifest_url.clone()).await?
+            }
+        };
+        desired.push(lockfile.pin(manifest)?);
+    }
+    let plan = plan_exact(app, &profile_path, &installed, &desired, &resolutions).await?;
+    perform_installation(app, profile_id, plan.clone(), ch).await?;
+    Ok(plan)
+}
+
+/// インストールされているものが`desired`とちょうど同じになるような計画を立てる。
+async fn plan_exact(
+    app: &tauri::AppHandle,
+    profile_path: &std::path::Path,
+    installed: &[models::Manifest],
+    desired: &[models::Manifest],
+    resolutions: &std::collections::HashMap<
+        std::path::PathBuf,
+        crate::installer::UntrackedFileResolution,
+    >,
+) -> anyhow::Result<crate::installer::InstallPlan> {
+    let uninstall = installed
+        .iter()
+        .filter(|m| !desired.iter().any(|d| d.id == m.id))
+        .map(|m| m.id.clone())
+        .collect::<Vec<_>>();
+    let listings = crate::installer::cached_bundle_listings(
+        profile_path,
+        desired,
+        app.state::<crate::cache::DownloadCache>().inner(),
+    )
+    .await?;
+    Ok(crate::installer::InstallPlan::plan(
+        profile_path,
+        installed,
+        desired,
+        &uninstall,
+        resolutions,
+        &listings,
+    )
+    .await?)
+}
+
+pub async fn list_generations(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> anyhow::Result<Vec<crate::generation::GenerationSummary>> {
+    let profile_path = find_profile_path(app, profile_id).await?;
+    crate::generation::list_generations(&profile_path).await
+}
+
+/// プロファイルを`generation`の世代の状態に戻す。
+pub async fn rollback_to_generation(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    generation: u64,
+    resolutions: std::collections::HashMap<
+        std::path::PathBuf,
+        crate::installer::UntrackedFileResolution,
+    >,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> anyhow::Result<crate::installer::InstallPlan> {
+    let (profile_path, installed) = open_profile_with_installed(app, profile_id).await?;
+    let generation = crate::generation::Generation::load(&profile_path, generation).await?;
+    let desired = generation.pinned_manifests()?;
+    let plan = plan_exact(app, &profile_path, &installed, &desired, &resolutions).await?;
+    perform_plan(app, profile_id, plan.clone(), ch).await?;
+
+    let mut store = get_profile_store(app, profile_id).await?;
+    for (id, installed) in store.contents.iter_mut() {
+        if let Some(recorded) = generation.contents.get(id) {
+            installed.registry = recorded.registry.clone();
+        }
+    }
+    store.save().await?;
+    drop(store);
+    generation.restore_configurations(&profile_path).await?;
+
+    // NOTE: 戻した状態も新しい世代として記録するので、ロールバックも取り消せる
+    record_generation(app, profile_id).await;
+    Ok(plan)
+}
+
+/// プロファイルと、宣言された状態とのずれを調べる。
+///
+/// `lockfile`と`composition`のどちらも指定されない場合は、プロファイルの`au2ec/lock.yml`があればそれと比べる。
+/// `check_upstream`が`true`の場合は、`manifest_url`から最新のマニフェストを取得して比べる。
+pub async fn detect_drift(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    lockfile: Option<std::path::PathBuf>,
+    composition: Option<Vec<models::Manifest>>,
+    check_upstream: bool,
+) -> anyhow::Result<crate::drift::DriftReport> {
+    let (profile_path, installed) = open_profile_with_installed(app, profile_id).await?;
+    let mut report = crate::drift::detect_drift(&profile_path, &installed).await?;
+
+    if check_upstream {
+        let mut upstream = std::collections::HashMap::new();
+        for manifest in &installed {
+            let Some(manifest_url) = &manifest.manifest_url else {
+                continue;
+            };
+            match fetch_manifest_cached(app, manifest_url.0.clone()).await {
+                Ok(latest) => {
+                    upstream.insert(manifest.id.clone(), latest);
+                }
+                Err(e) => log::warn!("Failed to fetch manifest {manifest_url}: {e}"),
+            }
+        }
+        report.outdated = crate::drift::find_outdated(&installed, &upstream);
+    }
+
+    let default_lockfile = profile_path.join(crate::lockfile::Lockfile::relative_path());
+    let declared = match (lockfile, composition) {
+        (Some(path), _) => Some(crate::lockfile::Lockfile::load(&path).await?),
+        (None, Some(composition)) => Some(crate::lockfile::Lockfile::from_manifests(&composition)),
+        (None, None) if default_lockfile.exists() => {
+            Some(crate::lockfile::Lockfile::load(&default_lockfile).await?)
+        }
+        (None, None) => None,
+    };
+    report.declared = declared
+        .as_ref()
+        .map(|declared| crate::drift::compare_declared(declared, &installed));
+
+    if report.has_drift() {
+        log::info!("Drift detected in {profile_id}");
+    }
+    Ok(report)
+}
+
+pub async fn verify_profile(
+    app: &tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    repair: bool,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> anyhow::Result<std::collections::BTreeMap<models::ManifestId, crate::verify::VerifyResult>> {
+    let (profile_path, installed) = open_profile_with_installed(app, profile_id).await?;
+
+    let mut results = std::collections::BTreeMap::new();
+    let mut plan = crate::installer::InstallPlan::default();
+    for manifest in &installed {
+        let result = crate::verify::verify_manifest(&profile_path, manifest).await?;
+        let broken_files = result.broken_files();
+        if !broken_files.is_empty() {
+            log::warn!("{} has broken files: {:?}", manifest.id, broken_files);
+            plan.to_repair.push((manifest.clone(), broken_files));
+        }
+        results.insert(manifest.id.clone(), result);
+    }
+
+    if repair && !plan.to_repair.is_empty() {
+        let store = get_profile_store(app, profile_id).await?;
+        let registries = store
+            .contents
+            .iter()
+            .filter_map(|(id, installed)| Some((id.clone(), installed.registry.clone()?)))
+            .collect();
+        let settings = crate::utils::open_index_store(app).await?.settings.clone();
+        crate::transaction::Transaction::recover(&profile_path).await?;
+        plan.perform(
+            &profile_path,
+            &install_options(app, &settings),
+            ch,
+            &mut ProfileStoreUpdater { store, registries },
+        )
+        .await?;
+    }
+
+    Ok(results)
+}
+
+async fn find_source_registries(
+    app: &tauri::AppHandle,
+    manifests: impl Iterator<Item = &models::Manifest>,
+) -> anyhow::Result<std::collections::HashMap<models::ManifestId, url::Url>> {
+    let index_store = crate::utils::open_index_store(app).await?;
+    let registry_urls: Vec<url::Url> = index_store.registries.values().cloned().collect();
+    drop(index_store);
+
+    let mut registries = Vec::new();
+    for registry_url in registry_urls {
+        match fetch_registry_cached(registry_url.clone()).await {
+            Ok(registry) => registries.push((registry_url, registry)),
+            Err(e) => log::warn!("Failed to fetch registry {registry_url}: {e}"),
+        }
+    }
+
+    Ok(manifests
+        .filter_map(|manifest| {
+            registries
+                .iter()
+                .find(|(_, registry)| registry.contents.iter().any(|c| c.id == manifest.id))
+                .map(|(url, _)| (manifest.id.clone(), url.clone()))
+        })
+        .collect())
+}
+
+pub async fn recover_transactions(app: &tauri::AppHandle) -> anyhow::Result<()> {
+    let index_store = crate::utils::open_index_store(app).await?;
+    let profiles = index_store.profiles.clone();
     drop(index_store);
 
-    plan.perform(&profile_path, ch).await?;
+    for profile in profiles.values() {
+        if !profile.path.exists() {
+            continue;
+        }
+        if let Err(e) = crate::transaction::Transaction::recover(&profile.path).await {
+            log::error!(
+                "Failed to recover transactions in {:?}: {e:?}",
+                profile.path
+            );
+        }
+    }
 
     Ok(())
 }
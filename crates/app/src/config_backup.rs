@@ -0,0 +1,392 @@
+use async_zip::tokio::write::ZipFileWriter;
+use futures::StreamExt;
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+use crate::installer::{DataDirPathExt, DATA_DIR};
+use crate::transaction::transactional;
+
+pub static CONFIG_BACKUPS_DIR: &str = "config_backups";
+
+static TIMESTAMP_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// アンインストール時に作られた、設定ファイルのバックアップ。
+/// `au2ec/config_backups/{マニフェストのID}/{日時}_{バージョン}.zip`に保存される。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct ConfigBackup {
+    pub manifest_id: crate::models::ManifestId,
+    pub version: String,
+    pub file_name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub size: u64,
+}
+
+/// バックアップに含まれるファイル。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct ConfigBackupEntry {
+    /// データディレクトリからの相対パス。
+    pub path: String,
+    pub size: u64,
+}
+
+/// 古いバックアップを削除する条件。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackupRetention {
+    /// マニフェストごとに残す最大の数。
+    pub max_count: Option<usize>,
+    /// 残す最大の日数。
+    pub max_age_days: Option<u64>,
+}
+
+fn backups_dir(root: &std::path::Path) -> std::path::PathBuf {
+    root.join(DATA_DIR).join(CONFIG_BACKUPS_DIR)
+}
+
+/// `manifest`の設定ファイル（ディレクトリの場合はその中のファイル）の絶対パス。
+pub async fn configuration_files(
+    root: &std::path::Path,
+    manifest: &crate::models::Manifest,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let mut files = vec![];
+    for config in manifest.configurations.iter() {
+        let abs_path = config.path.to_absolute_path(root);
+        // NOTE: 設定ファイルはまだ作られていないこともあるし、ディレクトリではなくファイルのこともある
+        if !abs_path.exists() {
+            continue;
+        }
+        if abs_path.is_file() {
+            files.push(abs_path);
+            continue;
+        }
+        let mut walk = async_walkdir::WalkDir::new(&abs_path);
+        while let Some(entry) = walk.next().await {
+            let entry = entry?;
+            if entry.file_type().await?.is_file() {
+                files.push(entry.path().to_path_buf());
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// `file`をzipに入れる際のエントリ名。データディレクトリからの相対パスになる。
+pub fn entry_name(root: &std::path::Path, file: &std::path::Path) -> String {
+    // NOTE: Windowsでも復元できるように、区切り文字は`/`にする
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `files`を`{prefix}{データディレクトリからの相対パス}`という名前で`zip`に書き込む。
+/// `on_file`にはファイルの番号、ファイルの数、データディレクトリからの相対パスが渡されます。
+pub async fn write_entries<W: futures::AsyncWrite + Unpin>(
+    zip: &mut async_zip::base::write::ZipFileWriter<W>,
+    root: &std::path::Path,
+    prefix: &str,
+    files: &[std::path::PathBuf],
+    mut on_file: impl FnMut(usize, usize, &str) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    for (i, file) in files.iter().enumerate() {
+        let name = entry_name(root, file);
+        on_file(i, files.len(), &name)?;
+
+        let entry = async_zip::ZipEntryBuilder::new(
+            format!("{prefix}{name}").into(),
+            async_zip::Compression::Deflate,
+        )
+        .build();
+        let mut writer = zip.write_entry_stream(entry).await?;
+        futures::io::copy(
+            &mut fs_err::tokio::File::open(file).await?.compat(),
+            &mut writer,
+        )
+        .await?;
+        writer.close().await?;
+    }
+    Ok(())
+}
+
+/// `archive`の`{prefix}{マニフェストのID}/`以下にある設定ファイルを`target`（データディレクトリ）に復元し、
+/// 復元したファイルの相対パスを返す。
+pub async fn restore_entries(
+    archive: &std::path::Path,
+    prefix: &str,
+    target: &std::path::Path,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let mut extractor = crate::archive::open(archive, &Default::default()).await?;
+    let mut files = std::collections::BTreeMap::<crate::models::ManifestId, Vec<String>>::new();
+    for name in extractor.list().await? {
+        let Some((id, _)) = name
+            .strip_prefix(prefix)
+            .and_then(|name| name.split_once('/'))
+        else {
+            continue;
+        };
+        files.entry(id.parse()?).or_default().push(name);
+    }
+
+    let mut restored = vec![];
+    for (id, names) in files {
+        let id_prefix = format!("{prefix}{}/", id.as_str());
+        let placed = transactional(target, &id, async |transaction| {
+            let staging_dir = transaction.staging_dir();
+            let mut targets = crate::archive::ExtractTargets::new();
+            let mut placed = vec![];
+            for name in names {
+                let relative_path = std::path::PathBuf::from(crate::archive::sanitize_entry_path(
+                    name.strip_prefix(&id_prefix).unwrap(),
+                )?);
+                targets.insert(name, vec![staging_dir.join(&relative_path)]);
+                placed.push(relative_path);
+            }
+            extractor.extract(&targets).await?;
+            for relative_path in &placed {
+                transaction
+                    .place(&staging_dir.join(relative_path), relative_path)
+                    .await?;
+            }
+            Ok(placed)
+        })
+        .await?;
+        restored.extend(placed);
+    }
+    Ok(restored)
+}
+
+/// `manifest`の設定ファイルをバックアップする。
+/// `on_file`にはファイルの番号、ファイルの数、データディレクトリからの相対パスが渡されます。
+///
+/// 設定ファイルが1つも無い場合は何もせずに`None`を返します。
+pub async fn backup_configuration(
+    root: &std::path::Path,
+    manifest: &crate::models::Manifest,
+    on_file: impl FnMut(usize, usize, &str) -> anyhow::Result<()>,
+) -> anyhow::Result<Option<std::path::PathBuf>> {
+    let configuration_files = configuration_files(root, manifest).await?;
+    if configuration_files.is_empty() {
+        return Ok(None);
+    }
+
+    let version = manifest
+        .version
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let backup_dir = backups_dir(root).join(manifest.id.as_str());
+    let backup_path = backup_dir.join(format!(
+        "{}_{version}.zip",
+        chrono::Local::now().format(TIMESTAMP_FORMAT)
+    ));
+    fs_err::tokio::create_dir_all(&backup_dir).await?;
+
+    let zip = fs_err::tokio::File::create(&backup_path).await?;
+    let mut zip = ZipFileWriter::new(zip.compat_write());
+    write_entries(&mut zip, root, "", &configuration_files, on_file).await?;
+    zip.close().await?;
+
+    Ok(Some(backup_path))
+}
+
+/// バックアップの一覧を新しい順に返す。`manifest_id`を指定した場合は、そのマニフェストのものだけを返す。
+pub async fn list_backups(
+    root: &std::path::Path,
+    manifest_id: Option<&crate::models::ManifestId>,
+) -> anyhow::Result<Vec<ConfigBackup>> {
+    let dir = backups_dir(root);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups = vec![];
+    let mut manifest_dirs = fs_err::tokio::read_dir(&dir).await?;
+    while let Some(manifest_dir) = manifest_dirs.next_entry().await? {
+        if !manifest_dir.file_type().await?.is_dir() {
+            continue;
+        }
+        let Ok(id) = manifest_dir
+            .file_name()
+            .to_string_lossy()
+            .parse::<crate::models::ManifestId>()
+        else {
+            continue;
+        };
+        if manifest_id.is_some_and(|manifest_id| manifest_id != &id) {
+            continue;
+        }
+        let mut files = fs_err::tokio::read_dir(manifest_dir.path()).await?;
+        while let Some(file) = files.next_entry().await? {
+            let file_name = file.file_name().to_string_lossy().to_string();
+            let Some((created_at, version)) = file_name
+                .strip_suffix(".zip")
+                .and_then(|stem| stem.split_once('_'))
+            else {
+                continue;
+            };
+            let Ok(created_at) =
+                chrono::NaiveDateTime::parse_from_str(created_at, TIMESTAMP_FORMAT)
+            else {
+                continue;
+            };
+            backups.push(ConfigBackup {
+                manifest_id: id.clone(),
+                version: version.to_owned(),
+                file_name: file_name.clone(),
+                created_at,
+                size: file.metadata().await?.len(),
+            });
+        }
+    }
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+/// バックアップのファイルのパス。`file_name`がファイル名でない場合や、存在しない場合はエラーにする。
+fn backup_file(
+    root: &std::path::Path,
+    manifest_id: &crate::models::ManifestId,
+    file_name: &str,
+) -> anyhow::Result<std::path::PathBuf> {
+    let path = backups_dir(root).join(manifest_id.as_str()).join(file_name);
+    if std::path::Path::new(file_name).file_name() != Some(std::ffi::OsStr::new(file_name))
+        || !path.is_file()
+    {
+        anyhow::bail!(
+            "#config_backup_not_found[{}]",
+            serde_json::to_string(file_name).unwrap()
+        );
+    }
+    Ok(path)
+}
+
+/// バックアップに含まれるファイルの一覧。
+pub async fn preview_backup(
+    root: &std::path::Path,
+    manifest_id: &crate::models::ManifestId,
+    file_name: &str,
+) -> anyhow::Result<Vec<ConfigBackupEntry>> {
+    let path = backup_file(root, manifest_id, file_name)?;
+    let zip = async_zip::tokio::read::fs::ZipFileReader::new(&path).await?;
+    Ok(zip
+        .file()
+        .entries()
+        .iter()
+        .filter(|entry| !entry.dir().unwrap_or(false))
+        .map(|entry| ConfigBackupEntry {
+            path: crate::archive::decode_zip_filename(entry.filename(), None),
+            size: entry.uncompressed_size(),
+        })
+        .collect())
+}
+
+/// `root`にあるバックアップを`target`（データディレクトリ）に復元し、復元したファイルの相対パスを返す。
+/// 既にあるファイルは上書きされます。
+pub async fn restore_backup(
+    root: &std::path::Path,
+    manifest_id: &crate::models::ManifestId,
+    file_name: &str,
+    target: &std::path::Path,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let path = backup_file(root, manifest_id, file_name)?;
+    transactional(target, manifest_id, async |transaction| {
+        let mut extractor = crate::archive::open(&path, &Default::default()).await?;
+        let staging_dir = transaction.staging_dir();
+        let mut targets = crate::archive::ExtractTargets::new();
+        let mut restored = vec![];
+        for name in extractor.list().await? {
+            let relative_path =
+                std::path::PathBuf::from(crate::archive::sanitize_entry_path(&name)?);
+            targets.insert(name, vec![staging_dir.join(&relative_path)]);
+            restored.push(relative_path);
+        }
+        extractor.extract(&targets).await?;
+        for relative_path in &restored {
+            transaction
+                .place(&staging_dir.join(relative_path), relative_path)
+                .await?;
+        }
+        Ok(restored)
+    })
+    .await
+}
+
+/// `retention`に従って古いバックアップを削除する。
+pub async fn prune_backups(
+    root: &std::path::Path,
+    retention: &BackupRetention,
+) -> anyhow::Result<()> {
+    let now = chrono::Local::now().naive_local();
+    let mut counts = std::collections::HashMap::<crate::models::ManifestId, usize>::new();
+    for backup in list_backups(root, None).await? {
+        let count = counts.entry(backup.manifest_id.clone()).or_default();
+        *count += 1;
+        let too_many = retention.max_count.is_some_and(|max| *count > max);
+        let too_old = retention
+            .max_age_days
+            .is_some_and(|days| now - backup.created_at > chrono::Duration::days(days as i64));
+        if too_many || too_old {
+            let path = backups_dir(root)
+                .join(backup.manifest_id.as_str())
+                .join(&backup.file_name);
+            fs_err::tokio::remove_file(&path).await?;
+            log::info!("Pruned configuration backup {path:?}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_and_prune_backups() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let id_a = "test-a".parse::<crate::models::ManifestId>().unwrap();
+        let id_b = "test-b".parse::<crate::models::ManifestId>().unwrap();
+        let now = chrono::Local::now().naive_local();
+        for (id, days_ago) in [(&id_a, 0), (&id_a, 1), (&id_a, 30), (&id_b, 2)] {
+            let dir = backups_dir(root).join(id.as_str());
+            fs_err::tokio::create_dir_all(&dir).await.unwrap();
+            let created_at = now - chrono::Duration::days(days_ago);
+            fs_err::tokio::write(
+                dir.join(format!("{}_1.0.0.zip", created_at.format(TIMESTAMP_FORMAT))),
+                "",
+            )
+            .await
+            .unwrap();
+        }
+
+        let backups = list_backups(root, Some(&id_a)).await.unwrap();
+        assert_eq!(backups.len(), 3);
+        assert!(backups
+            .windows(2)
+            .all(|pair| pair[0].created_at > pair[1].created_at));
+        assert!(backups.iter().all(|backup| backup.version == "1.0.0"));
+
+        prune_backups(
+            root,
+            &BackupRetention {
+                max_count: Some(2),
+                max_age_days: Some(7),
+            },
+        )
+        .await
+        .unwrap();
+        let remaining = list_backups(root, None).await.unwrap();
+        assert_eq!(
+            remaining
+                .iter()
+                .map(|backup| backup.manifest_id.as_str())
+                .collect::<Vec<_>>(),
+            ["test-a", "test-a", "test-b"]
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use futures::StreamExt;
+use sha2::Digest;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// 途中までダウンロードしたファイルについての情報。`{ファイル名}.part.json`に保存される。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PartialMeta {
+    url: url::Url,
+    /// `If-Range`に使う値（強いETag、またはLast-Modified）。
+    validator: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub max_retries: u32,
+    pub initial_backoff: std::time::Duration,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: std::time::Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub bytes: u64,
+    pub total_bytes: Option<u64>,
+    /// 再開したときの位置。最初からダウンロードしている場合は0。
+    pub resumed_from: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadOutcome {
+    /// `If-None-Match`に一致したため、ダウンロードしなかった。
+    NotModified,
+    Downloaded {
+        etag: Option<String>,
+        /// ダウンロードしたファイルのSHA256ハッシュ値。
+        sha256: String,
+    },
+}
+
+#[derive(Debug)]
+enum AttemptError {
+    /// 再試行すれば成功する可能性があるエラー。
+    Transient(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+impl From<std::io::Error> for AttemptError {
+    fn from(e: std::io::Error) -> Self {
+        AttemptError::Fatal(e.into())
+    }
+}
+
+/// `url`を`dest`にダウンロードする。
+///
+/// ダウンロード中のファイルは`{dest}.part`に置かれ、接続が切れた場合はサーバーが対応していれば
+/// `Range`/`If-Range`を使って続きからダウンロードします。
+/// 一時的なエラーの場合は`options`に従って再試行します。
+pub async fn download(
+    client: &reqwest::Client,
+    url: &url::Url,
+    dest: &std::path::Path,
+    if_none_match: Option<&str>,
+    options: &DownloadOptions,
+    mut on_progress: impl FnMut(DownloadProgress) -> anyhow::Result<()>,
+) -> anyhow::Result<DownloadOutcome> {
+    let mut attempt = 0;
+    loop {
+        match try_download(client, url, dest, if_none_match, &mut on_progress).await {
+            Ok(outcome) => return Ok(outcome),
+            Err(AttemptError::Transient(e)) if attempt < options.max_retries => {
+                let backoff = options.initial_backoff * 2u32.pow(attempt);
+                attempt += 1;
+                log::warn!(
+                    "Download of {url} failed, retrying in {backoff:?} ({attempt}/{}): {e:?}",
+                    options.max_retries
+                );
+                tokio::time::sleep(backoff).await;
+            }
+            Err(AttemptError::Transient(e) | AttemptError::Fatal(e)) => return Err(e),
+        }
+    }
+}
+
+async fn try_download(
+    client: &reqwest::Client,
+    url: &url::Url,
+    dest: &std::path::Path,
+    if_none_match: Option<&str>,
+    on_progress: &mut impl FnMut(DownloadProgress) -> anyhow::Result<()>,
+) -> Result<DownloadOutcome, AttemptError> {
+    let part_path = with_suffix(dest, ".part");
+    let meta_path = with_suffix(dest, ".part.json");
+
+    let mut resume = None;
+    if let (Ok(part), Ok(meta)) = (
+        fs_err::tokio::metadata(&part_path).await,
+        fs_err::tokio::read(&meta_path).await,
+    ) {
+        match serde_json::from_slice::<PartialMeta>(&meta) {
+            Ok(meta) if &meta.url == url && part.len() > 0 => {
+                resume = Some((part.len(), meta.validator))
+            }
+            _ => {}
+        }
+    }
+
+    let mut request = client.get(url.as_str());
+    match &resume {
+        Some((offset, validator)) => {
+            request = request
+                .header(reqwest::header::RANGE, format!("bytes={offset}-"))
+                .header(reqwest::header::IF_RANGE, validator);
+        }
+        None => {
+            if let Some(etag) = if_none_match {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+        }
+    }
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| AttemptError::Transient(e.into()))?;
+
+    let status = resp.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(DownloadOutcome::NotModified);
+    }
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        remove_partial(&part_path, &meta_path).await?;
+        return Err(AttemptError::Transient(anyhow::anyhow!(
+            "Range not satisfiable: {url}"
+        )));
+    }
+    if status.is_server_error()
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+    {
+        return Err(AttemptError::Transient(anyhow::anyhow!(
+            "Server returned {status}: {url}"
+        )));
+    }
+    if !status.is_success() {
+        return Err(AttemptError::Fatal(anyhow::anyhow!(
+            "Server returned {status}: {url}"
+        )));
+    }
+
+    let resumed_from = match (status, &resume) {
+        (reqwest::StatusCode::PARTIAL_CONTENT, Some((offset, _))) => {
+            if content_range_start(&resp) != Some(*offset) {
+                remove_partial(&part_path, &meta_path).await?;
+                return Err(AttemptError::Transient(anyhow::anyhow!(
+                    "Unexpected Content-Range: {url}"
+                )));
+            }
+            *offset
+        }
+        // サーバーが`Range`に対応していないか、ファイルが変わっている
+        _ => 0,
+    };
+
+    let etag = resp
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_owned);
+    match range_validator(&resp) {
+        Some(validator) => {
+            let meta = PartialMeta {
+                url: url.clone(),
+                validator,
+            };
+            fs_err::tokio::write(&meta_path, serde_json::to_vec(&meta).map_err(anyhow_fatal)?)
+                .await?;
+        }
+        None => {
+            if fs_err::tokio::metadata(&meta_path).await.is_ok() {
+                fs_err::tokio::remove_file(&meta_path).await?;
+            }
+        }
+    }
+
+    let total_bytes = resp.content_length().map(|len| len + resumed_from);
+    let mut hasher = sha2::Sha256::new();
+    let mut file = if resumed_from > 0 {
+        // NOTE: 続きからダウンロードする場合は、ダウンロード済みの部分を先にハッシュに入れておく
+        let mut file = fs_err::tokio::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&part_path)
+            .await?;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let read = file.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+        }
+        file
+    } else {
+        fs_err::tokio::File::create(&part_path).await?
+    };
+    let mut bytes = resumed_from;
+    on_progress(DownloadProgress {
+        bytes,
+        total_bytes,
+        resumed_from,
+    })
+    .map_err(AttemptError::Fatal)?;
+
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                file.flush().await?;
+                return Err(AttemptError::Transient(e.into()));
+            }
+        };
+        file.write_all(&chunk).await?;
+        hasher.update(&chunk);
+        bytes += chunk.len() as u64;
+        on_progress(DownloadProgress {
+            bytes,
+            total_bytes,
+            resumed_from,
+        })
+        .map_err(AttemptError::Fatal)?;
+    }
+    file.flush().await?;
+    drop(file);
+
+    if let Some(total_bytes) = total_bytes {
+        if bytes != total_bytes {
+            return Err(AttemptError::Transient(anyhow::anyhow!(
+                "Connection closed before the download completed: {url}"
+            )));
+        }
+    }
+
+    fs_err::tokio::rename(&part_path, dest).await?;
+    if fs_err::tokio::metadata(&meta_path).await.is_ok() {
+        fs_err::tokio::remove_file(&meta_path).await?;
+    }
+    Ok(DownloadOutcome::Downloaded {
+        etag,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
+
+/// `Range`リクエストで取得した範囲。本文はまだ読んでいない。
+#[derive(Debug)]
+pub struct RangeResponse {
+    pub start: u64,
+    /// ファイル全体の大きさ。
+    pub total: u64,
+    /// 次のリクエストの`If-Range`に使う値。
+    pub validator: Option<String>,
+    response: reqwest::Response,
+}
+
+impl RangeResponse {
+    /// 本文を全て読み込む。
+    pub async fn bytes(self) -> anyhow::Result<Vec<u8>> {
+        Ok(self.response.bytes().await?.to_vec())
+    }
+
+    /// 本文を`writer`の今の位置に書き込み、書き込んだ大きさを返す。
+    /// 本文が`limit`バイトより長い場合はエラーを返します。
+    pub async fn write_to(
+        self,
+        writer: &mut (impl tokio::io::AsyncWrite + Unpin),
+        limit: u64,
+        mut on_progress: impl FnMut(u64) -> anyhow::Result<()>,
+    ) -> anyhow::Result<u64> {
+        let mut written = 0u64;
+        let mut stream = self.response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            written += chunk.len() as u64;
+            anyhow::ensure!(written <= limit, "Range response is longer than requested");
+            writer.write_all(&chunk).await?;
+            on_progress(written)?;
+        }
+        Ok(written)
+    }
+}
+
+/// `url`の`range`（`bytes=`に続く部分）を取得する。
+///
+/// サーバーが`Range`に対応していない場合や、`if_range`が一致せずファイル全体が返ってきた場合は
+/// 本文を読まずに`None`を返します。
+pub async fn fetch_range(
+    client: &reqwest::Client,
+    url: &url::Url,
+    range: &str,
+    if_range: Option<&str>,
+) -> anyhow::Result<Option<RangeResponse>> {
+    let mut request = client
+        .get(url.as_str())
+        .header(reqwest::header::RANGE, format!("bytes={range}"));
+    if let Some(validator) = if_range {
+        request = request.header(reqwest::header::IF_RANGE, validator);
+    }
+    let resp = request.send().await?.error_for_status()?;
+    if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return Ok(None);
+    }
+    let Some((start, Some(total))) = content_range(&resp) else {
+        return Ok(None);
+    };
+    let validator = range_validator(&resp);
+    Ok(Some(RangeResponse {
+        start,
+        total,
+        validator,
+        response: resp,
+    }))
+}
+
+/// `If-Range`に使う値。弱いETagは`If-Range`に使えないため、その場合はLast-Modifiedを使う。
+fn range_validator(resp: &reqwest::Response) -> Option<String> {
+    let header = |name| {
+        resp.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    };
+    header(reqwest::header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(reqwest::header::LAST_MODIFIED))
+}
+
+fn anyhow_fatal(e: impl Into<anyhow::Error>) -> AttemptError {
+    AttemptError::Fatal(e.into())
+}
+
+fn with_suffix(path: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+async fn remove_partial(
+    part_path: &std::path::Path,
+    meta_path: &std::path::Path,
+) -> std::io::Result<()> {
+    for path in [part_path, meta_path] {
+        if fs_err::tokio::metadata(path).await.is_ok() {
+            fs_err::tokio::remove_file(path).await?;
+        }
+    }
+    Ok(())
+}
+
+/// `Content-Range: bytes {start}-{end}/{total}`の`start`。
+fn content_range_start(resp: &reqwest::Response) -> Option<u64> {
+    content_range(resp).map(|(start, _)| start)
+}
+
+/// `Content-Range: bytes {start}-{end}/{total}`の`start`と`total`。`total`が`*`の場合は`None`。
+fn content_range(resp: &reqwest::Response) -> Option<(u64, Option<u64>)> {
+    let content_range = resp
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let (range, total) = content_range.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split('-').next()?.trim().parse().ok()?;
+    Some((start, total.trim().parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1回目のリクエストでは途中で接続を切り、2回目は`Range`に従って続きを返すサーバー。
+    async fn flaky_server(
+        body: Vec<u8>,
+        cut_at: usize,
+    ) -> (url::Url, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = url::Url::parse(&format!(
+            "http://{}/bundle.zip",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for i in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap().to_ascii_lowercase();
+                let range_start = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+                requests.push(request);
+
+                let response = match range_start {
+                    Some(start) => {
+                        let mut response = format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+                            body.len() - start,
+                            start,
+                            body.len() - 1,
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(&body[start..]);
+                        response
+                    }
+                    None => {
+                        let mut response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        let end = if i == 0 { cut_at } else { body.len() };
+                        response.extend_from_slice(&body[..end]);
+                        response
+                    }
+                };
+                socket.write_all(&response).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_resume_download() {
+        let body = (0..100_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let (url, server) = flaky_server(body.clone(), 40_000).await;
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs_err::tokio::create_dir_all(&dir).await.unwrap();
+        let dest = dir.join("bundle.zip");
+
+        let mut progress = Vec::new();
+        let outcome = download(
+            &reqwest::Client::new(),
+            &url,
+            &dest,
+            None,
+            &DownloadOptions {
+                max_retries: 3,
+                initial_backoff: std::time::Duration::from_millis(10),
+            },
+            |p| {
+                progress.push(p);
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        let requests = server.await.unwrap();
+        assert_eq!(
+            outcome,
+            DownloadOutcome::Downloaded {
+                etag: Some("\"v1\"".to_owned()),
+                sha256: format!("{:x}", sha2::Sha256::digest(&body)),
+            }
+        );
+        assert_eq!(fs_err::tokio::read(&dest).await.unwrap(), body);
+        assert!(requests[1].contains("range: bytes=40000-"));
+        assert!(requests[1].contains("if-range: \"v1\""));
+        let last = progress.last().unwrap();
+        assert_eq!(last.resumed_from, 40_000);
+        assert_eq!(last.bytes, body.len() as u64);
+        assert_eq!(last.total_bytes, Some(body.len() as u64));
+        assert!(!with_suffix(&dest, ".part").exists());
+    }
+}
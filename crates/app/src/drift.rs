@@ -0,0 +1,208 @@
+use crate::models::AsVersion;
+
+/// プロファイルと、その宣言された状態とのずれ。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DriftReport {
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    /// アプリの外でインストールされた、どのマニフェストにも属していないファイル。`RootType`ごとにまとめる。
+    pub untracked: std::collections::BTreeMap<String, Vec<crate::orphan::OrphanFile>>,
+    /// ユーザーによって変更されたファイル。
+    pub modified: std::collections::BTreeMap<crate::models::ManifestId, Vec<std::path::PathBuf>>,
+    /// ストアに記録されているのに、無くなっているファイル。
+    pub missing: std::collections::BTreeMap<crate::models::ManifestId, Vec<std::path::PathBuf>>,
+    /// `manifest_url`の先が新しいバージョンになっているマニフェスト。
+    pub outdated: Vec<OutdatedManifest>,
+    /// ロックファイルや構成など、宣言された状態とのずれ。指定しなかった場合は`None`。
+    pub declared: Option<DeclaredDrift>,
+}
+
+impl DriftReport {
+    pub fn has_drift(&self) -> bool {
+        !self.untracked.is_empty()
+            || !self.modified.is_empty()
+            || !self.missing.is_empty()
+            || !self.outdated.is_empty()
+            || self
+                .declared
+                .as_ref()
+                .is_some_and(|declared| !declared.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OutdatedManifest {
+    pub id: crate::models::ManifestId,
+    pub installed: String,
+    pub upstream: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DeclaredDrift {
+    /// 宣言されているのに、インストールされていないマニフェスト。
+    pub not_installed: Vec<crate::models::ManifestId>,
+    /// インストールされているのに、宣言されていないマニフェスト。
+    pub not_declared: Vec<crate::models::ManifestId>,
+    /// 宣言とバージョンが違うマニフェスト。
+    pub version_mismatch: Vec<VersionMismatch>,
+}
+
+impl DeclaredDrift {
+    pub fn is_empty(&self) -> bool {
+        self.not_installed.is_empty()
+            && self.not_declared.is_empty()
+            && self.version_mismatch.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VersionMismatch {
+    pub id: crate::models::ManifestId,
+    pub declared: String,
+    pub installed: String,
+}
+
+/// ディスク上のファイルと`installed`を比べる。
+/// `outdated`と`declared`は空のままなので、呼び出し側で埋める。
+pub async fn detect_drift(
+    root: &std::path::Path,
+    installed: &[crate::models::Manifest],
+) -> anyhow::Result<DriftReport> {
+    let mut report = DriftReport {
+        checked_at: chrono::Utc::now(),
+        untracked: crate::orphan::list_orphans(root, installed).await?,
+        modified: Default::default(),
+        missing: Default::default(),
+        outdated: vec![],
+        declared: None,
+    };
+    for manifest in installed {
+        let result = crate::verify::verify_manifest(root, manifest).await?;
+        if !result.modified.is_empty() {
+            report.modified.insert(manifest.id.clone(), result.modified);
+        }
+        if !result.missing.is_empty() {
+            report.missing.insert(manifest.id.clone(), result.missing);
+        }
+    }
+    Ok(report)
+}
+
+/// `installed`のうち、`upstream`（`manifest_url`から取得し直したもの）の方が新しいマニフェスト。
+pub fn find_outdated(
+    installed: &[crate::models::Manifest],
+    upstream: &std::collections::HashMap<crate::models::ManifestId, crate::models::Manifest>,
+) -> Vec<OutdatedManifest> {
+    installed
+        .iter()
+        .filter_map(|manifest| {
+            let latest = upstream.get(&manifest.id)?;
+            (latest.as_version() > manifest.as_version()).then(|| OutdatedManifest {
+                id: manifest.id.clone(),
+                installed: manifest.version.to_string(),
+                upstream: latest.version.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `installed`を`lockfile`と比べる。構成（インストールしたいマニフェストの一覧）と比べる場合は、
+/// そこから作ったロックファイルを渡す。
+pub fn compare_declared(
+    lockfile: &crate::lockfile::Lockfile,
+    installed: &[crate::models::Manifest],
+) -> DeclaredDrift {
+    let mut drift = DeclaredDrift {
+        not_installed: lockfile
+            .manifests
+            .keys()
+            .filter(|id| !installed.iter().any(|manifest| &manifest.id == *id))
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+    for manifest in installed {
+        let Some(locked) = lockfile.manifests.get(&manifest.id) else {
+            drift.not_declared.push(manifest.id.clone());
+            continue;
+        };
+        let version = manifest.version.to_string();
+        if version != locked.version {
+            drift.version_mismatch.push(VersionMismatch {
+                id: manifest.id.clone(),
+                declared: locked.version.clone(),
+                installed: version,
+            });
+        }
+    }
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(id: &str, version: &str) -> crate::models::Manifest {
+        serde_json::from_value(serde_json::json!({
+            "manifest_version": 1,
+            "id": id,
+            "name": id,
+            "summary": "",
+            "version": version,
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "resources": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compare_declared() {
+        let locked = |version: &str| crate::lockfile::LockedManifest {
+            version: version.to_owned(),
+            version_number: None,
+            manifest_url: None,
+            resources: vec![],
+            bundles: Default::default(),
+            manifest: None,
+        };
+        let lockfile = crate::lockfile::Lockfile {
+            lockfile_version: crate::lockfile::LOCKFILE_VERSION,
+            manifests: [
+                ("test-a".parse().unwrap(), locked("1.0.0")),
+                ("test-b".parse().unwrap(), locked("1.0.0")),
+            ]
+            .into(),
+        };
+        let installed = [manifest("test-a", "1.1.0"), manifest("test-c", "1.0.0")];
+        assert_eq!(
+            compare_declared(&lockfile, &installed),
+            DeclaredDrift {
+                not_installed: vec!["test-b".parse().unwrap()],
+                not_declared: vec!["test-c".parse().unwrap()],
+                version_mismatch: vec![VersionMismatch {
+                    id: "test-a".parse().unwrap(),
+                    declared: "1.0.0".to_owned(),
+                    installed: "1.1.0".to_owned(),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_outdated() {
+        let installed = [manifest("test-a", "1.0.0"), manifest("test-b", "2.0.0")];
+        let upstream = [
+            ("test-a".parse().unwrap(), manifest("test-a", "1.2.0")),
+            ("test-b".parse().unwrap(), manifest("test-b", "2.0.0")),
+        ]
+        .into();
+        assert_eq!(
+            find_outdated(&installed, &upstream),
+            [OutdatedManifest {
+                id: "test-a".parse().unwrap(),
+                installed: "1.0.0".to_owned(),
+                upstream: "1.2.0".to_owned(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use async_zip::tokio::write::ZipFileWriter;
+use tokio_util::compat::TokioAsyncWriteCompatExt;
+
+use crate::installer::DataDirPathExt;
+
+static ENVIRONMENT_FILE: &str = "environment.json";
+static CONFIGURATIONS_DIR: &str = "configurations";
+
+/// 環境のエクスポートの形式のバージョン。
+pub const ENVIRONMENT_FORMAT_VERSION: u32 = 1;
+
+/// エクスポートされた環境。アーカイブの`environment.json`に保存される。
+///
+/// 設定ファイルはアーカイブの`configurations/{マニフェストのID}/{データディレクトリからの相対パス}`に入る。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Environment {
+    pub format_version: u32,
+    pub name: String,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    pub contents: std::collections::BTreeMap<crate::models::ManifestId, PinnedManifest>,
+}
+
+/// バージョンを固定したマニフェスト。インポート時はこのマニフェストをそのままインストールする。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PinnedManifest {
+    #[serde(default)]
+    pub registry: Option<url::Url>,
+    pub manifest: crate::models::Manifest,
+}
+
+/// `root`にインストールされている`store`の中身と設定ファイルを、`dest`のアーカイブに書き出す。
+/// 使い捨てのファイルは含めません。
+pub async fn export_environment(
+    root: &std::path::Path,
+    store: &crate::store::ProfileStore,
+    dest: &std::path::Path,
+) -> anyhow::Result<Environment> {
+    let environment = Environment {
+        format_version: ENVIRONMENT_FORMAT_VERSION,
+        name: store.name.clone(),
+        exported_at: chrono::Utc::now(),
+        contents: store
+            .contents
+            .iter()
+            .map(|(id, installed)| {
+                (
+                    id.clone(),
+                    PinnedManifest {
+                        registry: installed.registry.clone(),
+                        manifest: installed.manifest.clone(),
+                    },
+                )
+            })
+            .collect(),
+    };
+    let disposables = store
+        .contents
+        .values()
+        .flat_map(|installed| installed.manifest.disposables.iter())
+        .map(|disposable| disposable.path.to_absolute_path(root))
+        .collect::<Vec<_>>();
+
+    let zip = fs_err::tokio::File::create(dest).await?;
+    let mut zip = ZipFileWriter::new(zip.compat_write());
+    let entry = async_zip::ZipEntryBuilder::new(
+        ENVIRONMENT_FILE.to_owned().into(),
+        async_zip::Compression::Deflate,
+    )
+    .build();
+    zip.write_entry_whole(entry, &serde_json::to_vec_pretty(&environment)?)
+        .await?;
+
+    for (id, installed) in &store.contents {
+        let files = crate::config_backup::configuration_files(root, &installed.manifest)
+            .await?
+            .into_iter()
+            .filter(|file| {
+                !disposables
+                    .iter()
+                    .any(|disposable| file.starts_with(disposable))
+            })
+            .collect::<Vec<_>>();
+        crate::config_backup::write_entries(
+            &mut zip,
+            root,
+            &format!("{CONFIGURATIONS_DIR}/{}/", id.as_str()),
+            &files,
+            |_, _, _| Ok(()),
+        )
+        .await?;
+    }
+    zip.close().await?;
+
+    Ok(environment)
+}
+
+/// アーカイブから`environment.json`を読む。
+pub async fn read_environment(archive: &std::path::Path) -> anyhow::Result<Environment> {
+    let zip = async_zip::tokio::read::fs::ZipFileReader::new(archive).await?;
+    let Some(index) = zip
+        .file()
+        .entries()
+        .iter()
+        .position(|entry| entry.filename().as_str().ok() == Some(ENVIRONMENT_FILE))
+    else {
+        anyhow::bail!("#invalid_environment_archive");
+    };
+    let mut content = String::new();
+    zip.reader_with_entry(index)
+        .await?
+        .read_to_string_checked(&mut content)
+        .await?;
+    let environment: Environment = serde_json::from_str(&content)?;
+    if environment.format_version > ENVIRONMENT_FORMAT_VERSION {
+        anyhow::bail!(
+            "#unsupported_environment_version[{}]",
+            serde_json::to_string(&environment.format_version).unwrap()
+        );
+    }
+    Ok(environment)
+}
+
+/// アーカイブに含まれる設定ファイルを`target`（データディレクトリ）に復元し、復元したファイルの相対パスを返す。
+pub async fn restore_configurations(
+    archive: &std::path::Path,
+    target: &std::path::Path,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    crate::config_backup::restore_entries(archive, &format!("{CONFIGURATIONS_DIR}/"), target).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_export_and_restore_environment() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let base = temp_dir.path();
+        let source = base.join("source");
+        let target = base.join("target");
+        fs_err::tokio::create_dir_all(source.join("Plugin/test/cache"))
+            .await
+            .unwrap();
+        fs_err::tokio::create_dir_all(&target).await.unwrap();
+        fs_err::tokio::write(source.join("Plugin/test/config.ini"), "a=1")
+            .await
+            .unwrap();
+        fs_err::tokio::write(source.join("Plugin/test/cache/data.bin"), "cache")
+            .await
+            .unwrap();
+
+        let manifest: crate::models::Manifest = serde_json::from_value(serde_json::json!({
+            "manifest_version": 1,
+            "id": "test-config",
+            "name": "test-config",
+            "summary": "",
+            "version": "1.2.3",
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "resources": [],
+            "configurations": [{ "path": "$plugin/test/" }],
+            "disposables": [{ "path": "$plugin/test/cache/" }],
+        }))
+        .unwrap();
+        let store = crate::store::ProfileStore {
+            name: "source".to_owned(),
+            contents: [(
+                manifest.id.clone(),
+                crate::store::InstalledManifest {
+                    manifest,
+                    installed_at: chrono::Utc::now(),
+                    registry: None,
+                },
+            )]
+            .into(),
+        };
+
+        let archive = base.join("environment.zip");
+        export_environment(&source, &store, &archive).await.unwrap();
+        let environment = read_environment(&archive).await.unwrap();
+        assert_eq!(environment.name, "source");
+        assert_eq!(
+            environment
+                .contents
+                .values()
+                .map(|pinned| pinned.manifest.version.to_string())
+                .collect::<Vec<_>>(),
+            ["1.2.3"]
+        );
+
+        let restored = restore_configurations(&archive, &target).await.unwrap();
+        assert_eq!(
+            restored,
+            [std::path::PathBuf::from("Plugin/test/config.ini")]
+        );
+        assert_eq!(
+            fs_err::tokio::read_to_string(target.join("Plugin/test/config.ini"))
+                .await
+                .unwrap(),
+            "a=1"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use async_zip::tokio::write::ZipFileWriter;
+use tokio_util::compat::TokioAsyncWriteCompatExt;
+
+use crate::installer::DATA_DIR;
+use crate::receipt::Receipt;
+
+pub static GENERATIONS_DIR: &str = "generations";
+
+static GENERATION_FILE: &str = "generation.json";
+static CONFIGURATIONS_FILE: &str = "configurations.zip";
+
+/// インストールが完了した時点でのプロファイルの状態。
+/// `au2ec/generations/{番号}/`に、`generation.json`と設定ファイルの`configurations.zip`として保存される。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Generation {
+    pub number: u64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub contents:
+        std::collections::BTreeMap<crate::models::ManifestId, crate::store::InstalledManifest>,
+    pub receipts: std::collections::BTreeMap<crate::models::ManifestId, Receipt>,
+}
+
+/// 一覧に表示するための、世代の概要。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct GenerationSummary {
+    pub number: u64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// マニフェストのIDと、そのバージョン。
+    pub manifests: std::collections::BTreeMap<crate::models::ManifestId, String>,
+}
+
+impl Generation {
+    fn dir(root: &std::path::Path, number: u64) -> std::path::PathBuf {
+        generations_dir(root).join(number.to_string())
+    }
+
+    pub fn summary(&self) -> GenerationSummary {
+        GenerationSummary {
+            number: self.number,
+            created_at: self.created_at,
+            manifests: self
+                .contents
+                .iter()
+                .map(|(id, installed)| (id.clone(), installed.manifest.version.to_string()))
+                .collect(),
+        }
+    }
+
+    /// `root`の今の状態を新しい世代として記録する。
+    pub async fn record(
+        root: &std::path::Path,
+        contents: &std::collections::BTreeMap<
+            crate::models::ManifestId,
+            crate::store::InstalledManifest,
+        >,
+    ) -> anyhow::Result<Self> {
+        let number = generation_numbers(root)
+            .await?
+            .last()
+            .map_or(1, |last| last + 1);
+        let mut receipts = std::collections::BTreeMap::new();
+        for id in contents.keys() {
+            if let Some(receipt) = Receipt::load(root, id).await? {
+                receipts.insert(id.clone(), receipt);
+            }
+        }
+        let generation = Self {
+            number,
+            created_at: chrono::Utc::now(),
+            contents: contents.clone(),
+            receipts,
+        };
+
+        // NOTE: 途中で失敗したものが世代として見えないように、一時ディレクトリに書いてから移動する
+        let dir = Self::dir(root, number);
+        let tmp_dir = dir.with_extension("tmp");
+        if tmp_dir.exists() {
+            fs_err::tokio::remove_dir_all(&tmp_dir).await?;
+        }
+        fs_err::tokio::create_dir_all(&tmp_dir).await?;
+        let zip = fs_err::tokio::File::create(tmp_dir.join(CONFIGURATIONS_FILE)).await?;
+        let mut zip = ZipFileWriter::new(zip.compat_write());
+        for (id, installed) in contents {
+            let files =
+                crate::config_backup::configuration_files(root, &installed.manifest).await?;
+            crate::config_backup::write_entries(
+                &mut zip,
+                root,
+                &format!("{}/", id.as_str()),
+                &files,
+                |_, _, _| Ok(()),
+            )
+            .await?;
+        }
+        zip.close().await?;
+        fs_err::tokio::write(
+            tmp_dir.join(GENERATION_FILE),
+            serde_json::to_vec_pretty(&generation)?,
+        )
+        .await?;
+        fs_err::tokio::rename(&tmp_dir, &dir).await?;
+
+        Ok(generation)
+    }
+
+    pub async fn load(root: &std::path::Path, number: u64) -> anyhow::Result<Self> {
+        let path = Self::dir(root, number).join(GENERATION_FILE);
+        if !path.exists() {
+            anyhow::bail!(
+                "#generation_not_found[{}]",
+                serde_json::to_string(&number).unwrap()
+            );
+        }
+        Ok(serde_json::from_slice(&fs_err::tokio::read(&path).await?)?)
+    }
+
+    /// この世代の時点でインストールされていたマニフェスト。
+    /// マニフェストにSHA256ハッシュ値が無いリソースは、レシートの値で補う。
+    ///
+    /// NOTE: ハッシュ値があればダウンロードキャッシュから取り出せる
+    pub fn pinned_manifests(&self) -> anyhow::Result<Vec<crate::models::Manifest>> {
+        let lockfile = crate::lockfile::Lockfile {
+            lockfile_version: crate::lockfile::LOCKFILE_VERSION,
+            manifests: self
+                .contents
+                .iter()
+                .map(|(id, installed)| {
+                    (
+                        id.clone(),
+                        crate::lockfile::LockedManifest::new(
+                            &installed.manifest,
+                            self.receipts.get(id),
+                        ),
+                    )
+                })
+                .collect(),
+        };
+        self.contents
+            .values()
+            .map(|installed| lockfile.pin(installed.manifest.clone()))
+            .collect()
+    }
+
+    /// この世代の設定ファイルを`root`に復元する。
+    pub async fn restore_configurations(
+        &self,
+        root: &std::path::Path,
+    ) -> anyhow::Result<Vec<std::path::PathBuf>> {
+        let archive = Self::dir(root, self.number).join(CONFIGURATIONS_FILE);
+        if !archive.exists() {
+            return Ok(vec![]);
+        }
+        crate::config_backup::restore_entries(&archive, "", root).await
+    }
+}
+
+fn generations_dir(root: &std::path::Path) -> std::path::PathBuf {
+    root.join(DATA_DIR).join(GENERATIONS_DIR)
+}
+
+/// 記録されている世代の番号を、古い順に返す。
+async fn generation_numbers(root: &std::path::Path) -> anyhow::Result<Vec<u64>> {
+    let dir = generations_dir(root);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut numbers = vec![];
+    let mut entries = fs_err::tokio::read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if let Ok(number) = entry.file_name().to_string_lossy().parse::<u64>() {
+            numbers.push(number);
+        }
+    }
+    numbers.sort_unstable();
+    Ok(numbers)
+}
+
+/// 記録されている世代の一覧を、新しい順に返す。
+pub async fn list_generations(root: &std::path::Path) -> anyhow::Result<Vec<GenerationSummary>> {
+    let mut generations = vec![];
+    for number in generation_numbers(root).await?.into_iter().rev() {
+        match Generation::load(root, number).await {
+            Ok(generation) => generations.push(generation.summary()),
+            Err(e) => log::warn!("Failed to load generation {number}: {e:?}"),
+        }
+    }
+    Ok(generations)
+}
+
+/// 新しいものから`max_count`個を残して、古い世代を削除する。
+pub async fn prune_generations(root: &std::path::Path, max_count: usize) -> anyhow::Result<()> {
+    let numbers = generation_numbers(root).await?;
+    for number in numbers.iter().rev().skip(max_count) {
+        fs_err::tokio::remove_dir_all(Generation::dir(root, *number)).await?;
+        log::info!("Pruned generation {number}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_and_prune_generations() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs_err::tokio::create_dir_all(root.join("Plugin/test"))
+            .await
+            .unwrap();
+        fs_err::tokio::write(root.join("Plugin/test/config.ini"), "a=1")
+            .await
+            .unwrap();
+        let manifest: crate::models::Manifest = serde_json::from_value(serde_json::json!({
+            "manifest_version": 1,
+            "id": "test-generation",
+            "name": "test-generation",
+            "summary": "",
+            "version": "1.0.0",
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "resources": [],
+            "configurations": [{ "path": "$plugin/test/config.ini" }],
+        }))
+        .unwrap();
+        let contents = [(
+            manifest.id.clone(),
+            crate::store::InstalledManifest {
+                manifest,
+                installed_at: chrono::Utc::now(),
+                registry: None,
+            },
+        )]
+        .into();
+
+        for expected in 1..=3 {
+            let generation = Generation::record(root, &contents).await.unwrap();
+            assert_eq!(generation.number, expected);
+        }
+        prune_generations(root, 2).await.unwrap();
+        let generations = list_generations(root).await.unwrap();
+        assert_eq!(
+            generations
+                .iter()
+                .map(|generation| generation.number)
+                .collect::<Vec<_>>(),
+            [3, 2]
+        );
+
+        fs_err::tokio::write(root.join("Plugin/test/config.ini"), "a=2")
+            .await
+            .unwrap();
+        let generation = Generation::load(root, 2).await.unwrap();
+        assert_eq!(generation.pinned_manifests().unwrap().len(), 1);
+        let restored = generation.restore_configurations(root).await.unwrap();
+        assert_eq!(
+            restored,
+            [std::path::PathBuf::from("Plugin/test/config.ini")]
+        );
+        assert_eq!(
+            fs_err::tokio::read_to_string(root.join("Plugin/test/config.ini"))
+                .await
+                .unwrap(),
+            "a=1"
+        );
+    }
+}
@@ -1,10 +1,17 @@
-use async_zip::tokio::write::ZipFileWriter;
-use futures::StreamExt;
-use tokio::io::AsyncWriteExt;
-use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+use futures::{AsyncReadExt, StreamExt};
+use sha2::Digest;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+use crate::cache::{CacheKey, DownloadCache};
+use crate::download::{download, DownloadOptions, DownloadOutcome, DownloadProgress};
+use crate::models::AsVersion;
+use crate::receipt::{Receipt, ReceiptFile};
+use crate::transaction::Transactions;
+use crate::vfs::{FileOperation, FileSystem, StagedFile, VirtualFs, Workspace};
 
 pub static DATA_DIR: &str = "au2ec";
 pub static TEMP_DIR: &str = "temp";
+pub static DOWNLOADS_DIR: &str = "downloads";
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub enum PathUsageType {
@@ -23,10 +30,31 @@ pub struct PathUsage {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub struct ConflictInfo {
     pub path: std::path::PathBuf,
+    pub existing_manifest_id: crate::models::ManifestId,
     pub existing: PathUsage,
+    pub new_manifest_id: crate::models::ManifestId,
     pub new: PathUsage,
 }
 
+/// どのマニフェストにも属していない、既に存在するファイル。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct UntrackedFile {
+    pub path: std::path::PathBuf,
+    pub manifest_id: crate::models::ManifestId,
+}
+
+/// 管理外のファイルをどう扱うか。
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UntrackedFileResolution {
+    /// インストールを中止する。
+    Abort,
+    /// `au2ec/backups`にバックアップしてから上書きする。
+    Overwrite,
+    /// 既存のファイルをそのまま使い、マニフェストの所有とする。
+    Adopt,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum InstallProgress {
@@ -36,6 +64,8 @@ pub enum InstallProgress {
         name: String,
         bytes: u64,
         total_bytes: Option<u64>,
+        /// 途中から再開した場合の開始位置。
+        resumed_from: u64,
     },
     Remove {
         file: usize,
@@ -52,6 +82,18 @@ pub enum InstallProgress {
         num_files: usize,
         name: String,
     },
+    /// 更新時に、変更が無いためそのまま残したリソース。
+    Keep {
+        file: usize,
+        num_files: usize,
+        name: String,
+    },
+    /// 更新時に、前のバージョンのファイルを置き換えたリソース。
+    Replace {
+        file: usize,
+        num_files: usize,
+        name: String,
+    },
     Complete,
 
     Error {
@@ -82,6 +124,16 @@ impl AsPathUsage for crate::models::ManifestConfigurationsItem {
     }
 }
 
+impl AsPathUsage for crate::receipt::ReceiptFile {
+    fn to_path_usage(&self) -> PathUsage {
+        PathUsage {
+            path: self.path.clone(),
+            usage_type: PathUsageType::Resource,
+            scope: self.scope.clone(),
+        }
+    }
+}
+
 impl AsPathUsage for crate::models::ManifestDisposablesItem {
     fn to_path_usage(&self) -> PathUsage {
         PathUsage {
@@ -97,9 +149,28 @@ pub enum PlanError {
     #[error("#conflicting_file_paths[{}]", serde_json::to_string(.0).unwrap())]
     Conflict(Vec<ConflictInfo>),
 
+    #[error("#untracked_files[{}]", serde_json::to_string(.0).unwrap())]
+    UntrackedFiles(Vec<UntrackedFile>),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum InstallError {
+    #[error(
+        "#hash_mismatch[{},{},{}]",
+        serde_json::to_string(.name).unwrap(),
+        serde_json::to_string(.expected).unwrap(),
+        serde_json::to_string(.actual).unwrap()
+    )]
+    HashMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+}
+
 pub trait DataDirPathExt {
     fn to_absolute_path(&self, root: &std::path::Path) -> std::path::PathBuf;
 
@@ -112,38 +183,111 @@ impl DataDirPathExt for crate::models::DataDirRelativePath {
     }
 
     fn as_relative_path(&self) -> std::path::PathBuf {
-        match self.root {
-            crate::models::RootType::Data => std::path::PathBuf::from(&self.path),
-            root_type => std::path::PathBuf::from(match root_type {
-                crate::models::RootType::Alias => "Alias",
-                crate::models::RootType::Palette => "Default",
-                crate::models::RootType::Figure => "Figure",
-                crate::models::RootType::Language => "Language",
-                crate::models::RootType::Plugin => "Plugin",
-                crate::models::RootType::Script => "Script",
-                crate::models::RootType::Transition => "Transition",
-                crate::models::RootType::Theme => const_format::formatc!("{DATA_DIR}/themes"),
-                crate::models::RootType::Data => unreachable!(),
-            })
-            .join(&self.path),
+        match root_type_dir(self.root) {
+            None => std::path::PathBuf::from(&self.path),
+            Some(dir) => std::path::PathBuf::from(dir).join(&self.path),
         }
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// `root_type`が指すディレクトリの、データディレクトリからの相対パス。`$data`の場合は`None`。
+pub fn root_type_dir(root_type: crate::models::RootType) -> Option<&'static str> {
+    match root_type {
+        crate::models::RootType::Alias => Some("Alias"),
+        crate::models::RootType::Palette => Some("Default"),
+        crate::models::RootType::Figure => Some("Figure"),
+        crate::models::RootType::Language => Some("Language"),
+        crate::models::RootType::Plugin => Some("Plugin"),
+        crate::models::RootType::Script => Some("Script"),
+        crate::models::RootType::Transition => Some("Transition"),
+        crate::models::RootType::Theme => Some(const_format::formatc!("{DATA_DIR}/themes")),
+        crate::models::RootType::Data => None,
+    }
+}
+
+/// `path`が`root_type_dir`のいずれか、またはその親ディレクトリであるかどうか。
+pub fn is_root_type_dir(path: &std::path::Path) -> bool {
+    use strum::IntoEnumIterator;
+    crate::models::RootType::iter()
+        .filter_map(root_type_dir)
+        .any(|dir| std::path::Path::new(dir).starts_with(path))
+}
+
+/// `Plugin`や`Script`などのディレクトリ以下のファイルを、データディレクトリからの相対パスで列挙する。
+pub async fn list_root_type_files(
+    root: &std::path::Path,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    use strum::IntoEnumIterator;
+
+    let mut files = Vec::new();
+    for dir in crate::models::RootType::iter().filter_map(root_type_dir) {
+        let abs_dir = root.join(dir);
+        if !abs_dir.is_dir() {
+            continue;
+        }
+        let mut walk = async_walkdir::WalkDir::new(&abs_dir);
+        while let Some(entry) = walk.next().await {
+            let entry = entry?;
+            if entry.file_type().await?.is_file() {
+                files.push(entry.path().strip_prefix(root)?.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[derive(Debug)]
+pub struct InstallOptions<'a> {
+    /// 同時にダウンロードするファイルの数。
+    pub download_concurrency: usize,
+    pub cache: Option<&'a DownloadCache>,
+    /// キャッシュの最大サイズ（バイト）。
+    pub cache_max_size: u64,
+}
+
+pub trait InstallObserver: Send {
+    /// マニフェストの処理が完了したときに呼ばれる。`manifest`が`None`の場合はアンインストールされたことを示す。
+    fn on_complete(
+        &mut self,
+        id: &crate::models::ManifestId,
+        manifest: Option<&crate::models::Manifest>,
+    ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send;
+}
+
+/// 何もしない。ドライランで使う。
+impl InstallObserver for () {
+    async fn on_complete(
+        &mut self,
+        _id: &crate::models::ManifestId,
+        _manifest: Option<&crate::models::Manifest>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct InstallPlan {
     pub to_keep: Vec<crate::models::Manifest>,
     pub to_uninstall: Vec<crate::models::Manifest>,
     pub to_update: Vec<(crate::models::Manifest, crate::models::Manifest)>,
     pub to_install: Vec<crate::models::Manifest>,
+    /// 壊れたファイルを再インストールするマニフェストと、そのファイル。
+    #[serde(default)]
+    pub to_repair: Vec<(crate::models::Manifest, Vec<std::path::PathBuf>)>,
+    /// 管理外のファイルとその扱い。
+    #[serde(default)]
+    pub untracked: std::collections::BTreeMap<std::path::PathBuf, UntrackedFileResolution>,
 }
 
 impl InstallPlan {
-    pub fn plan(
-        _root: &std::path::Path,
+    pub async fn plan(
+        root: &std::path::Path,
         existing: &[crate::models::Manifest],
         desired: &[crate::models::Manifest],
         uninstall: &[crate::models::ManifestId],
+        resolutions: &std::collections::HashMap<std::path::PathBuf, UntrackedFileResolution>,
+        listings: &BundleListings,
     ) -> Result<Self, PlanError> {
         let existing_ids: std::collections::HashMap<
             crate::models::ManifestId,
@@ -172,7 +316,7 @@ impl InstallPlan {
         }
 
         for (id, existing_manifest) in &existing_ids {
-            if !desired_ids.contains_key(id) && uninstall.contains(id) {
+            if !desired_ids.contains_key(id) && !uninstall.contains(id) {
                 to_keep.push(existing_manifest.clone());
             }
         }
@@ -183,49 +327,189 @@ impl InstallPlan {
             }
         }
 
-        // TODO: rootをチェックして既存ファイルがあったらエラーにする
+        let mut usages = Vec::new();
+        for manifest in &to_keep {
+            // インストール済みのものはレシートに記録された実際のファイルを使う
+            match Receipt::load(root, &manifest.id).await? {
+                Some(receipt) => usages.extend(
+                    receipt
+                        .files
+                        .iter()
+                        .map(|f| (manifest.id.clone(), f.to_path_usage())),
+                ),
+                None => usages.extend(
+                    manifest
+                        .resources
+                        .iter()
+                        .map(|r| (manifest.id.clone(), r.to_path_usage())),
+                ),
+            }
+            usages.extend(Self::non_resource_usages(manifest));
+        }
+        for manifest in to_install
+            .iter()
+            .chain(to_update.iter().map(|(_, new)| new))
+        {
+            // NOTE: バンドルの循環参照はダウンロードを始める前に検出する
+            nested_bundle_order(manifest)?;
+            usages.extend(Self::resource_usages(manifest, listings)?);
+            usages.extend(Self::non_resource_usages(manifest));
+        }
+        let conflicts = Self::find_conflicts(&usages);
+        if !conflicts.is_empty() {
+            return Err(PlanError::Conflict(conflicts));
+        }
 
-        let mut used_paths = std::collections::HashMap::new();
-        let mut conflicts = Vec::new();
-        for manifest in to_keep.iter().chain(to_install.iter()) {
-            for resource in &manifest.resources {
-                let new_path = resource.destination.as_relative_path();
-                if let Some(usage) = Self::check_conflict(&used_paths, &resource.to_path_usage()) {
-                    conflicts.push(ConflictInfo {
-                        path: new_path,
-                        existing: usage,
-                        new: resource.to_path_usage(),
-                    });
-                } else {
-                    used_paths.insert(new_path, resource.to_path_usage());
+        let untracked = Self::find_untracked_files(
+            root,
+            existing,
+            to_install
+                .iter()
+                .chain(to_update.iter().map(|(_, new)| new)),
+            listings,
+        )
+        .await?;
+        let mut unresolved = Vec::new();
+        let mut untracked_resolutions = std::collections::BTreeMap::new();
+        for file in untracked {
+            match resolutions.get(&file.path) {
+                Some(UntrackedFileResolution::Abort) | None => unresolved.push(file),
+                Some(resolution) => {
+                    untracked_resolutions.insert(file.path, *resolution);
                 }
             }
         }
+        if !unresolved.is_empty() {
+            return Err(PlanError::UntrackedFiles(unresolved));
+        }
 
         Ok(Self {
             to_keep,
             to_uninstall,
             to_update,
             to_install,
+            to_repair: Vec::new(),
+            untracked: untracked_resolutions,
         })
     }
 
-    fn check_conflict(
-        used_paths: &std::collections::HashMap<std::path::PathBuf, PathUsage>,
-        new_usage: &PathUsage,
-    ) -> Option<PathUsage> {
-        let new_path = new_usage.path.as_relative_path();
-        for (used_path, existing_usage) in used_paths {
-            if !Self::check_path_conflict(used_path, &new_path) {
-                continue;
+    /// インストール先に既に存在し、どのマニフェストにも属していないファイルを探す。
+    /// バンドルの中身が分かっている場合は、展開されるファイルだけを見る。
+    async fn find_untracked_files<'a>(
+        root: &std::path::Path,
+        existing: &[crate::models::Manifest],
+        installing: impl Iterator<Item = &'a crate::models::Manifest>,
+        listings: &BundleListings,
+    ) -> anyhow::Result<Vec<UntrackedFile>> {
+        let owned_paths = crate::receipt::owned_paths(root, existing).await?;
+
+        let mut untracked = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for manifest in installing {
+            for (_, usage) in Self::resource_usages(manifest, listings)? {
+                let relative_path = usage.path.as_relative_path();
+                // ルートディレクトリそのものを指している場合は全てのファイルが対象になってしまうので見ない
+                if is_root_type_dir(&relative_path) {
+                    continue;
+                }
+                let path = root.join(&relative_path);
+                let files =
+                    tokio::task::spawn_blocking(move || list_files_blocking(&path)).await??;
+                for file in files {
+                    let file = file.strip_prefix(root)?.to_path_buf();
+                    if owned_paths
+                        .iter()
+                        .any(|owned| Self::check_path_conflict(owned, &file))
+                    {
+                        continue;
+                    }
+                    if seen.insert(file.clone()) {
+                        untracked.push(UntrackedFile {
+                            path: file,
+                            manifest_id: manifest.id.clone(),
+                        });
+                    }
+                }
             }
-            if existing_usage == new_usage {
+        }
+        Ok(untracked)
+    }
+
+    /// バンドルの中身が分かっている場合は、実際に展開されるファイルを使う。
+    fn resource_usages(
+        manifest: &crate::models::Manifest,
+        listings: &BundleListings,
+    ) -> anyhow::Result<Vec<(crate::models::ManifestId, PathUsage)>> {
+        let mut usages = Vec::new();
+        for resource in &manifest.resources {
+            let names = match resource.source.scheme() {
+                "bundle" => listings.get(&(
+                    manifest.id.clone(),
+                    bundle_name(&resource.source)?.to_string(),
+                )),
+                _ => None,
+            };
+            let Some(names) = names else {
+                usages.push((manifest.id.clone(), resource.to_path_usage()));
                 continue;
+            };
+            for (_, destination) in bundle_entries(resource, names)? {
+                usages.push((
+                    manifest.id.clone(),
+                    PathUsage {
+                        path: destination,
+                        usage_type: PathUsageType::Resource,
+                        scope: resource.scope.as_deref().cloned(),
+                    },
+                ));
             }
-            return Some(existing_usage.clone());
         }
+        Ok(usages)
+    }
 
-        None
+    fn non_resource_usages(
+        manifest: &crate::models::Manifest,
+    ) -> impl Iterator<Item = (crate::models::ManifestId, PathUsage)> + '_ {
+        manifest
+            .configurations
+            .iter()
+            .map(|c| c.to_path_usage())
+            .chain(manifest.disposables.iter().map(|d| d.to_path_usage()))
+            .map(|usage| (manifest.id.clone(), usage))
+    }
+
+    fn find_conflicts(usages: &[(crate::models::ManifestId, PathUsage)]) -> Vec<ConflictInfo> {
+        let mut conflicts = Vec::new();
+        for (i, (existing_id, existing)) in usages.iter().enumerate() {
+            for (new_id, new) in &usages[i + 1..] {
+                // 同じマニフェスト内での重複は競合としない
+                if existing_id == new_id {
+                    continue;
+                }
+                let new_path = new.path.as_relative_path();
+                if !Self::check_path_conflict(&existing.path.as_relative_path(), &new_path) {
+                    continue;
+                }
+                if Self::is_shared_usage(existing, new) {
+                    continue;
+                }
+                conflicts.push(ConflictInfo {
+                    path: new_path,
+                    existing_manifest_id: existing_id.clone(),
+                    existing: existing.clone(),
+                    new_manifest_id: new_id.clone(),
+                    new: new.clone(),
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// 同じスコープ内の同じ種類のファイルは、複数のマニフェストで共有できる。
+    fn is_shared_usage(usage1: &PathUsage, usage2: &PathUsage) -> bool {
+        usage1.scope.is_some()
+            && usage1.scope == usage2.scope
+            && usage1.usage_type == usage2.usage_type
     }
 
     fn check_path_conflict(path1: &std::path::Path, path2: &std::path::Path) -> bool {
@@ -244,399 +528,1445 @@ impl InstallPlan {
     pub async fn perform(
         &self,
         root: &std::path::Path,
+        options: &InstallOptions<'_>,
+        ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
+        observer: &mut impl InstallObserver,
+    ) -> Result<(), anyhow::Error> {
+        // 先に全てのマニフェストのファイルをまとめてダウンロードしておく
+        let downloads_dir = root.join(DATA_DIR).join(TEMP_DIR).join(DOWNLOADS_DIR);
+        let manifests_to_download = self
+            .to_update
+            .iter()
+            .map(|(_, new)| new)
+            .chain(self.to_install.iter())
+            .chain(self.to_repair.iter().map(|(manifest, _)| manifest))
+            .collect::<Vec<_>>();
+        let kept = self.kept_resources(root).await;
+        let mut download_errors = Self::download_all(
+            &downloads_dir,
+            manifests_to_download.iter().copied(),
+            &kept,
+            options,
+            ch.clone(),
+        )
+        .await;
+        let failed_downloads = download_errors
+            .keys()
+            .cloned()
+            .collect::<std::collections::HashSet<_>>();
+        let result = self
+            .perform_with_sources(
+                &mut Transactions::new(root),
+                &Sources::Downloads(&downloads_dir),
+                &kept,
+                &mut download_errors,
+                ch,
+                observer,
+            )
+            .await;
+
+        // ダウンロードに失敗したものは、次回続きからダウンロードできるように残しておく
+        for manifest in manifests_to_download {
+            if failed_downloads.contains(&manifest.id) {
+                continue;
+            }
+            let dir = downloads_dir.join(manifest.id.to_string());
+            if let Err(e) = fs_err::tokio::remove_dir_all(&dir).await {
+                log::warn!("Failed to remove downloads directory: {e}");
+            }
+        }
+        result
+    }
+
+    /// ファイルを変更せずに、`perform`で行われるファイル操作の一覧を返す。
+    ///
+    /// `perform`と同じ処理を[`VirtualFs`]に対して行います。ダウンロードはしないため、
+    /// バンドルから取り出すファイルは`listings`に中身がある場合のみファイル単位になり、
+    /// 書き込むファイルの大きさは`cache`にある場合のみ分かります。
+    pub async fn dry_run(
+        &self,
+        root: &std::path::Path,
+        listings: &BundleListings,
+        cache: &DownloadCache,
+    ) -> anyhow::Result<Vec<FileOperation>> {
+        let kept = self.kept_resources(root).await;
+        let mut fs = VirtualFs::new(root);
+        let ch = tauri::ipc::Channel::new(|_| Ok(()));
+        self.perform_with_sources(
+            &mut fs,
+            &Sources::Cached { listings, cache },
+            &kept,
+            &mut std::collections::HashMap::new(),
+            ch,
+            &mut (),
+        )
+        .await?;
+        fs.into_operations()
+    }
+
+    /// 更新と修復で、ダウンロードせずにそのまま残すリソース。
+    async fn kept_resources(
+        &self,
+        root: &std::path::Path,
+    ) -> std::collections::HashMap<crate::models::ManifestId, KeptResources> {
+        let mut kept = std::collections::HashMap::new();
+        for (_, new) in &self.to_update {
+            match Self::unchanged_resources(root, new).await {
+                Ok(resources) => {
+                    kept.insert(new.id.clone(), resources);
+                }
+                Err(e) => log::warn!("Failed to check unchanged files of {}: {e:?}", new.id),
+            }
+        }
+        for (manifest, files) in &self.to_repair {
+            match Self::intact_resources(root, manifest, files).await {
+                Ok(resources) => {
+                    kept.insert(manifest.id.clone(), resources);
+                }
+                Err(e) => log::warn!("Failed to check intact files of {}: {e:?}", manifest.id),
+            }
+        }
+        kept
+    }
+
+    async fn perform_with_sources<W: Workspace>(
+        &self,
+        workspace: &mut W,
+        sources: &Sources<'_>,
+        kept: &std::collections::HashMap<crate::models::ManifestId, KeptResources>,
+        download_errors: &mut std::collections::HashMap<crate::models::ManifestId, anyhow::Error>,
         ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
+        observer: &mut impl InstallObserver,
     ) -> Result<(), anyhow::Error> {
         for manifest in &self.to_uninstall {
             let lch = ch.clone();
-            if let Err(e) = self.perform_uninstall(root, manifest, lch).await {
-                ch.send((
-                    manifest.id.clone(),
-                    InstallProgress::Error {
-                        message: format!("{}", e),
-                    },
-                ))?;
+            let result = workspace
+                .run(&manifest.id, async |fs| {
+                    self.perform_uninstall(manifest, fs, lch).await
+                })
+                .await;
+            if result.is_ok() {
+                observer.on_complete(&manifest.id, None).await?;
             }
+            Self::send_result(&ch, &manifest.id, result)?;
         }
         for (old, new) in &self.to_update {
+            if let Some(e) = download_errors.remove(&new.id) {
+                Self::send_result(&ch, &new.id, Err(e))?;
+                continue;
+            }
             let lch = ch.clone();
-            if let Err(e) = self.perform_update(root, old, new, lch).await {
-                ch.send((
-                    new.id.clone(),
-                    InstallProgress::Error {
-                        message: format!("{}", e),
-                    },
-                ))?;
+            let kept = kept.get(&new.id).cloned().unwrap_or_default();
+            let result = workspace
+                .run(&new.id, async |fs| {
+                    self.perform_update(old, new, kept, sources, fs, lch).await
+                })
+                .await;
+            if result.is_ok() {
+                observer.on_complete(&new.id, Some(new)).await?;
             }
+            Self::send_result(&ch, &new.id, result)?;
         }
         for manifest in &self.to_install {
+            if let Some(e) = download_errors.remove(&manifest.id) {
+                Self::send_result(&ch, &manifest.id, Err(e))?;
+                continue;
+            }
             let lch = ch.clone();
-            if let Err(e) = self.perform_install(root, manifest, lch).await {
-                ch.send((
-                    manifest.id.clone(),
-                    InstallProgress::Error {
-                        message: format!("{}", e),
-                    },
-                ))?;
+            let result = workspace
+                .run(&manifest.id, async |fs| {
+                    self.perform_install(
+                        manifest,
+                        None,
+                        &PreviousInstall::default(),
+                        sources,
+                        fs,
+                        lch,
+                    )
+                    .await
+                    .map(|_| ())
+                })
+                .await;
+            if result.is_ok() {
+                observer.on_complete(&manifest.id, Some(manifest)).await?;
+            }
+            Self::send_result(&ch, &manifest.id, result)?;
+        }
+        for (manifest, files) in &self.to_repair {
+            if let Some(e) = download_errors.remove(&manifest.id) {
+                Self::send_result(&ch, &manifest.id, Err(e))?;
+                continue;
             }
+            let lch = ch.clone();
+            let previous = PreviousInstall {
+                paths: Default::default(),
+                kept: kept.get(&manifest.id).cloned().unwrap_or_default(),
+            };
+            let result = workspace
+                .run(&manifest.id, async |fs| {
+                    self.perform_install(manifest, Some(files), &previous, sources, fs, lch)
+                        .await
+                        .map(|_| ())
+                })
+                .await;
+            if result.is_ok() {
+                observer.on_complete(&manifest.id, Some(manifest)).await?;
+            }
+            Self::send_result(&ch, &manifest.id, result)?;
+        }
+        Ok(())
+    }
+
+    /// `manifests`のファイルを、最大`options.download_concurrency`個ずつ並列でダウンロードする。
+    /// ファイルは`downloads_dir/{マニフェストのID}`に保存され、失敗したマニフェストのエラーを返す。
+    /// `kept`のリソースはダウンロードしない。
+    async fn download_all<'a>(
+        downloads_dir: &std::path::Path,
+        manifests: impl Iterator<Item = &'a crate::models::Manifest>,
+        kept: &std::collections::HashMap<crate::models::ManifestId, KeptResources>,
+        options: &InstallOptions<'_>,
+        ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
+    ) -> std::collections::HashMap<crate::models::ManifestId, anyhow::Error> {
+        let mut errors = std::collections::HashMap::new();
+        let mut jobs = Vec::new();
+        let no_kept = KeptResources::new();
+        for manifest in manifests {
+            let dir = downloads_dir.join(manifest.id.to_string());
+            if let Err(e) = fs_err::tokio::create_dir_all(&dir).await {
+                errors.insert(manifest.id.clone(), e.into());
+                continue;
+            }
+            let kept = kept.get(&manifest.id).unwrap_or(&no_kept);
+            jobs.extend(DownloadJob::for_manifest(manifest, &dir, kept));
+        }
+
+        let mut results = futures::stream::iter(jobs)
+            .map(|job| {
+                let ch = ch.clone();
+                async move {
+                    let result = job.download(options, &ch).await;
+                    (job.manifest_id, result)
+                }
+            })
+            .buffer_unordered(options.download_concurrency.max(1));
+        while let Some((id, result)) = results.next().await {
+            if let Err(e) = result {
+                errors.entry(id).or_insert(e);
+            }
+        }
+        errors
+    }
+
+    fn send_result(
+        ch: &tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
+        id: &crate::models::ManifestId,
+        result: anyhow::Result<()>,
+    ) -> Result<(), anyhow::Error> {
+        match result {
+            Ok(()) => ch.send((id.clone(), InstallProgress::Complete))?,
+            Err(e) => ch.send((
+                id.clone(),
+                InstallProgress::Error {
+                    message: format!("{}", e),
+                },
+            ))?,
         }
         Ok(())
     }
 
     async fn uninstall_internal(
         &self,
-        root: &std::path::Path,
+        fs: &mut impl FileSystem,
         usages: &[(crate::models::ManifestId, PathUsage)],
         ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
     ) -> Result<(), anyhow::Error> {
+        let usages = Self::exclude_shared_usages(fs, usages).await?;
         let total_files = usages.len();
+        let mut parent_dirs = std::collections::BTreeSet::new();
         for (i, (id, usage)) in usages.iter().enumerate() {
+            let relative_path = usage.path.as_relative_path();
             ch.send((
                 id.clone(),
                 InstallProgress::Remove {
                     file: i,
                     num_files: total_files,
-                    name: usage.path.as_relative_path().to_string_lossy().to_string(),
+                    name: relative_path.to_string_lossy().to_string(),
                 },
             ))?;
 
-            let abs_path = usage.path.to_absolute_path(root);
-            if abs_path.exists() {
-                tokio::fs::remove_file(&abs_path).await?;
+            fs.remove(&relative_path).await?;
+            parent_dirs.extend(
+                relative_path
+                    .ancestors()
+                    .skip(1)
+                    .filter(|p| !p.as_os_str().is_empty() && !is_root_type_dir(p))
+                    .map(|p| p.to_path_buf()),
+            );
+        }
+
+        // 空になったディレクトリを深い方から削除する
+        for dir in parent_dirs.iter().rev() {
+            if fs.is_empty_dir(dir).await? {
+                fs.remove(dir).await?;
             }
         }
 
         Ok(())
     }
 
-    async fn shallow_uninstall(
+    /// `usages`から、他のマニフェストのレシートにも記録されているパスを除く。
+    ///
+    /// NOTE: 同じスコープのファイルは複数のマニフェストで共有できるので、
+    ///       他のマニフェストが使っている間は削除しない
+    async fn exclude_shared_usages(
+        fs: &impl FileSystem,
+        usages: &[(crate::models::ManifestId, PathUsage)],
+    ) -> anyhow::Result<Vec<(crate::models::ManifestId, PathUsage)>> {
+        let others = fs
+            .receipts()
+            .await?
+            .into_iter()
+            .filter(|receipt| !usages.iter().any(|(id, _)| *id == receipt.id))
+            .flat_map(|receipt| receipt.files)
+            .map(|file| file.path.as_relative_path())
+            .collect::<Vec<_>>();
+        Ok(usages
+            .iter()
+            .filter(|(_, usage)| {
+                let path = usage.path.as_relative_path();
+                !others
+                    .iter()
+                    .any(|other| Self::check_path_conflict(other, &path))
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// インストール済みのファイルの一覧。レシートがない場合はマニフェストの`resources`から推測する。
+    async fn installed_usages(
         &self,
-        root: &std::path::Path,
+        fs: &impl FileSystem,
         manifest: &crate::models::Manifest,
-        ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
-    ) -> Result<(), anyhow::Error> {
-        self.uninstall_internal(
-            root,
-            &manifest
+    ) -> Result<Vec<(crate::models::ManifestId, PathUsage)>, anyhow::Error> {
+        Ok(match fs.receipt(&manifest.id).await? {
+            Some(receipt) => receipt
+                .files
+                .iter()
+                .map(|f| (manifest.id.clone(), f.to_path_usage()))
+                .collect(),
+            None => manifest
                 .resources
                 .iter()
                 .map(|r| (manifest.id.clone(), r.to_path_usage()))
-                .collect::<Vec<_>>(),
-            ch.clone(),
-        )
-        .await
+                .collect(),
+        })
     }
 
     async fn full_uninstall(
         &self,
-        root: &std::path::Path,
         manifest: &crate::models::Manifest,
+        fs: &mut impl FileSystem,
         ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
     ) -> Result<(), anyhow::Error> {
-        let mut usages = Vec::new();
-        usages.extend(
-            manifest
-                .resources
-                .iter()
-                .map(|r| (manifest.id.clone(), r.to_path_usage())),
-        );
+        let mut usages = self.installed_usages(fs, manifest).await?;
         usages.extend(
             manifest
                 .disposables
                 .iter()
                 .map(|d| (manifest.id.clone(), d.to_path_usage())),
         );
-        self.uninstall_internal(root, &usages, ch.clone()).await
+        self.uninstall_internal(fs, &usages, ch.clone()).await?;
+        fs.remove_receipt(&manifest.id).await
     }
 
     async fn backup_configuration(
         &self,
-        root: &std::path::Path,
         manifest: &crate::models::Manifest,
+        fs: &mut impl FileSystem,
         ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
     ) -> Result<(), anyhow::Error> {
-        let configuration_backup_dir = root.join(DATA_DIR).join("config_backups");
-        let configuration_backup = format!(
-            "{}_{}_{}.zip",
-            chrono::Local::now().format("%Y%m%d%H%M%S"),
-            &manifest.name,
-            &manifest.version
-        );
-
-        fs_err::tokio::create_dir_all(&configuration_backup_dir).await?;
-
-        let mut configuration_files = vec![];
-        for config in manifest.configurations.iter() {
-            let abs_path = config.path.to_absolute_path(root);
-            let mut walk = async_walkdir::WalkDir::new(&abs_path);
-            while let Some(entry) = walk.next().await {
-                let entry = entry?;
-                if entry.file_type().await?.is_file() {
-                    configuration_files.push(entry.path().to_path_buf());
-                }
-            }
-        }
-
-        let zip =
-            fs_err::tokio::File::open(configuration_backup_dir.join(&configuration_backup)).await?;
-        let mut zip = ZipFileWriter::new(zip.compat_write());
-        let num_files = configuration_files.len();
-        for (i, file) in configuration_files.into_iter().enumerate() {
+        fs.backup_configuration(manifest, |file, num_files, name| {
             ch.send((
                 manifest.id.clone(),
                 InstallProgress::Backup {
-                    file: i,
+                    file,
                     num_files,
-                    name: file
-                        .strip_prefix(root)
-                        .unwrap_or(&file)
-                        .to_string_lossy()
-                        .to_string(),
+                    name: name.to_owned(),
                 },
             ))?;
+            Ok(())
+        })
+        .await
+    }
 
-            let name = file
-                .strip_prefix(root)
-                .unwrap_or(&file)
-                .to_string_lossy()
-                .to_string();
-            let entry =
-                async_zip::ZipEntryBuilder::new(name.into(), async_zip::Compression::Deflate)
-                    .build();
-
-            let mut writer = zip.write_entry_stream(entry).await?;
-            futures::io::copy(
-                &mut fs_err::tokio::File::open(&file).await?.compat(),
-                &mut writer,
-            )
-            .await?;
+    /// 更新時に、前のバージョンからそのまま残せるリソースを探す。
+    /// レシートに記録されたSHA256ハッシュ値が新しいリソースのものと同じで、
+    /// インストールされているファイルも書き換えられていないものが対象。
+    async fn unchanged_resources(
+        root: &std::path::Path,
+        new: &crate::models::Manifest,
+    ) -> anyhow::Result<KeptResources> {
+        let mut kept = KeptResources::new();
+        let Some(receipt) = Receipt::load(root, &new.id).await? else {
+            return Ok(kept);
+        };
+        for resource in &new.resources {
+            let Some(sha256) = &resource.sha256 else {
+                continue;
+            };
+            let Some(file) = receipt.files.iter().find(|file| {
+                file.path == resource.destination && file.sha256.eq_ignore_ascii_case(sha256)
+            }) else {
+                continue;
+            };
+            match hash_file(&file.path.to_absolute_path(root)).await {
+                Ok((_, actual)) if actual.eq_ignore_ascii_case(sha256) => {
+                    kept.insert(resource.destination.clone(), vec![file.clone()]);
+                }
+                _ => {}
+            }
         }
-        zip.close().await?;
+        Ok(kept)
+    }
 
-        Ok(())
+    /// 修復時に、`broken`のファイルを含まないリソースを探す。
+    /// これらはダウンロードせず、レシートに記録されたファイルをそのまま残す。
+    async fn intact_resources(
+        root: &std::path::Path,
+        manifest: &crate::models::Manifest,
+        broken: &[std::path::PathBuf],
+    ) -> anyhow::Result<KeptResources> {
+        let mut kept = KeptResources::new();
+        let Some(receipt) = Receipt::load(root, &manifest.id).await? else {
+            return Ok(kept);
+        };
+        for resource in &manifest.resources {
+            let destination = resource.destination.as_relative_path();
+            if broken.iter().any(|path| path.starts_with(&destination)) {
+                continue;
+            }
+            let files = receipt
+                .files
+                .iter()
+                .filter(|file| file.path.as_relative_path().starts_with(&destination))
+                .cloned()
+                .collect();
+            kept.insert(resource.destination.clone(), files);
+        }
+        Ok(kept)
     }
 
+    /// `old`から`new`に更新する。変更の無いリソースはそのまま残し、`new`に無くなったファイルだけを削除する。
     async fn perform_update(
         &self,
-        root: &std::path::Path,
         old: &crate::models::Manifest,
         new: &crate::models::Manifest,
+        kept: KeptResources,
+        sources: &Sources<'_>,
+        fs: &mut impl FileSystem,
         ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
     ) -> Result<(), anyhow::Error> {
-        self.shallow_uninstall(root, old, ch.clone()).await?;
-        self.perform_install(root, new, ch.clone()).await
+        let usages = self.installed_usages(fs, old).await?;
+        let previous = PreviousInstall {
+            paths: usages
+                .iter()
+                .map(|(_, usage)| usage.path.as_relative_path())
+                .collect(),
+            kept,
+        };
+        let installed = self
+            .perform_install(new, None, &previous, sources, fs, ch.clone())
+            .await?;
+
+        let removed = usages
+            .into_iter()
+            .filter(|(_, usage)| {
+                let path = usage.path.as_relative_path();
+                !installed
+                    .iter()
+                    .any(|file| file.path.as_relative_path().starts_with(&path))
+            })
+            .collect::<Vec<_>>();
+        self.uninstall_internal(fs, &removed, ch).await
     }
 
     async fn perform_uninstall(
         &self,
-        root: &std::path::Path,
         manifest: &crate::models::Manifest,
+        fs: &mut impl FileSystem,
         ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
     ) -> Result<(), anyhow::Error> {
-        self.backup_configuration(root, manifest, ch.clone())
-            .await?;
-        self.full_uninstall(root, manifest, ch.clone()).await?;
-        ch.send((manifest.id.clone(), InstallProgress::Complete))?;
+        self.backup_configuration(manifest, fs, ch.clone()).await?;
+        self.full_uninstall(manifest, fs, ch.clone()).await?;
         Ok(())
     }
 
+    /// `manifest`をインストールし、レシートに記録したファイルを返す。
+    /// `only`が指定された場合は、そのパス以下のファイルのみを配置する。
     async fn perform_install(
         &self,
-        root: &std::path::Path,
         manifest: &crate::models::Manifest,
+        only: Option<&[std::path::PathBuf]>,
+        previous: &PreviousInstall,
+        sources: &Sources<'_>,
+        fs: &mut impl FileSystem,
         ch: tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
-    ) -> Result<(), anyhow::Error> {
-        let temp_dir = tempfile::TempDir::with_prefix_in(
-            root.join(DATA_DIR).join(TEMP_DIR),
-            format!("{}-", &manifest.id),
-        )?;
-        fs_err::tokio::create_dir_all(&temp_dir).await?;
-        let files_to_download = manifest
-            .resources
-            .iter()
-            .filter(|c| c.source.scheme() == "http" || c.source.scheme() == "https")
-            .map(|c| {
-                (
-                    either::Either::Left(c),
-                    temp_dir.path().join(url_to_file_name(&c.source)),
-                )
-            })
-            .chain(manifest.bundles.iter().flat_map(|c| {
-                c.iter()
-                    .map(|r| (either::Either::Right(r), temp_dir.path().join(&**r.0)))
-            }))
-            .collect::<Vec<_>>();
-        let num_files = files_to_download.len();
-
-        for (i, (file, destination)) in files_to_download.into_iter().enumerate() {
-            let source = match file {
-                either::Either::Left(r) => &url::Url::clone(&r.source),
-                either::Either::Right((_name, url)) => &url::Url::clone(url),
-            };
-            let name = destination.to_string_lossy().to_string();
-            ch.send((
-                manifest.id.clone(),
-                InstallProgress::Download {
+    ) -> Result<Vec<ReceiptFile>, anyhow::Error> {
+        let total_files = manifest.resources.len();
+        for (i, resource) in manifest.resources.iter().enumerate() {
+            let relative_path = resource.destination.as_relative_path();
+            let name = relative_path.to_string_lossy().to_string();
+            let progress = if previous.kept.contains_key(&resource.destination) {
+                InstallProgress::Keep {
                     file: i,
-                    num_files,
-                    name: name.clone(),
-                    bytes: 0,
-                    total_bytes: None,
-                },
-            ))?;
-
-            assert!(source.scheme() == "http" || source.scheme() == "https");
-
-            let resp = reqwest::get(source.as_str()).await?;
-            let total_bytes = resp.content_length();
-            let mut stream = resp.bytes_stream();
-
-            let mut file = fs_err::tokio::File::create(&destination).await?;
-            let mut downloaded_bytes = 0u64;
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk?;
-                file.write_all(&chunk).await?;
-                downloaded_bytes += chunk.len() as u64;
-                ch.send((
-                    manifest.id.clone(),
-                    InstallProgress::Download {
-                        file: i,
-                        num_files,
-                        name: name.clone(),
-                        bytes: downloaded_bytes,
-                        total_bytes,
-                    },
-                ))?;
-            }
-        }
-
-        let total_files = manifest.resources.len();
-        for (i, resource) in manifest.resources.iter().enumerate() {
-            ch.send((
-                manifest.id.clone(),
+                    num_files: total_files,
+                    name,
+                }
+            } else if previous
+                .paths
+                .iter()
+                .any(|path| path.starts_with(&relative_path))
+            {
+                InstallProgress::Replace {
+                    file: i,
+                    num_files: total_files,
+                    name,
+                }
+            } else {
                 InstallProgress::Install {
                     file: i,
                     num_files: total_files,
-                    name: resource
-                        .destination
-                        .as_relative_path()
-                        .to_string_lossy()
-                        .to_string(),
-                },
-            ))?;
-            let abs_path = resource.destination.to_absolute_path(root);
-            match resource.source.scheme() {
-                "http" | "https" => {
-                    let temp_file = temp_dir.path().join(url_to_file_name(&resource.source));
-                    if !temp_file.exists() {
-                        return Err(anyhow::anyhow!(
-                            "Temporary file not found: {}",
-                            temp_file.display()
-                        ));
-                    }
-                    if let Some(parent) = abs_path.parent() {
-                        fs_err::tokio::create_dir_all(parent).await?;
-                    }
-                    fs_err::tokio::rename(&temp_file, &abs_path).await?;
+                    name,
                 }
-                "bundle" => {
-                    let bundle_name = resource
-                        .source
-                        .host_str()
-                        .ok_or_else(|| anyhow::anyhow!("Invalid bundle URL: {}", resource.source))?
-                        .to_string();
-                    let bundle = manifest
-                        .bundles
-                        .iter()
-                        .flatten()
-                        .find(|(name, _)| name.as_str() == bundle_name)
-                        .ok_or_else(|| {
-                            anyhow::anyhow!("Bundle not found for resource: {}", resource.source)
-                        })?;
-                    let bundle_path = temp_dir.path().join(&**bundle.0);
-                    if !bundle_path.exists() {
-                        return Err(anyhow::anyhow!(
-                            "Temporary bundle file not found: {}",
-                            bundle_path.display()
-                        ));
-                    }
+            };
+            ch.send((manifest.id.clone(), progress))?;
+        }
+        let staged = match sources {
+            Sources::Downloads(downloads_dir) => {
+                let staging_dir = fs
+                    .staging_dir()
+                    .ok_or_else(|| anyhow::anyhow!("Staging directory is not available"))?;
+                let temp_dir = downloads_dir.join(manifest.id.to_string());
+                stage_resources(manifest, &previous.kept, &temp_dir, &staging_dir).await?
+            }
+            Sources::Cached { listings, cache } => {
+                preview_resources(manifest, &previous.kept, listings, cache).await?
+            }
+        };
 
-                    if let Some(parent) = abs_path.parent() {
-                        fs_err::tokio::create_dir_all(parent).await?;
+        let mut files = vec![];
+        let mut placed = std::collections::HashSet::new();
+        for (resource, staged_files) in manifest.resources.iter().zip(staged) {
+            if let Some(kept_files) = previous.kept.get(&resource.destination) {
+                for file in kept_files {
+                    if placed.insert(file.path.clone()) {
+                        files.push(ReceiptFile {
+                            scope: resource.scope.as_deref().cloned(),
+                            ..file.clone()
+                        });
                     }
-                    let mut zip = async_zip::tokio::read::seek::ZipFileReader::new(
-                        tokio::io::BufReader::new(fs_err::tokio::File::open(&bundle_path).await?)
-                            .compat(),
-                    )
-                    .await?;
-                    let wants_directory_source = resource.source.path().ends_with('/');
-                    let wants_directory_destination = resource.destination.path.ends_with('/');
-                    if wants_directory_source != wants_directory_destination {
-                        return Err(anyhow::anyhow!(
-                            "Resource source and destination must both be files or both be directories: source={}, destination={}",
-                            resource.source,
-                            resource.destination
-                        ));
-                    }
-                    let wants_directory = wants_directory_source;
-                    let entries = zip
-                        .file()
-                        .entries()
-                        .iter()
-                        .enumerate()
-                        .filter_map(|(i, e)| {
-                            let entry_path = e.filename().as_str().ok()?;
-                            if wants_directory {
-                                if !entry_path.starts_with(resource.source.path()) {
-                                    return None;
-                                }
-                                let relative_path = &entry_path[resource.source.path().len()..];
-                                Some((i, relative_path.to_owned()))
-                            } else if entry_path == resource.source.path() {
-                                Some((i, "".to_owned()))
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>();
-                    if entries.is_empty() {
-                        return Err(anyhow::anyhow!(
-                            "File not found in bundle: {}",
-                            resource.source.path()
-                        ));
+                }
+                continue;
+            }
+            for file in staged_files {
+                if !placed.insert(file.path.clone()) {
+                    continue;
+                }
+                let relative_path = file.path.as_relative_path();
+                let (size, sha256) = match self.untracked.get(&relative_path) {
+                    Some(UntrackedFileResolution::Adopt) => {
+                        hash_file(&fs.root().join(&relative_path)).await?
                     }
-                    if !wants_directory && entries.len() > 1 {
-                        return Err(anyhow::anyhow!(
-                            "Multiple files found in bundle for single file resource: {}",
-                            resource.source.path()
-                        ));
+                    // 修復時は壊れているファイル以外はそのままにする
+                    _ if only.is_some_and(|only| {
+                        !only.iter().any(|path| relative_path.starts_with(path))
+                    }) =>
+                    {
+                        file.hash().await?
                     }
-                    for (index, relative_path) in entries {
-                        let out_path = if wants_directory {
-                            abs_path.join(relative_path)
-                        } else {
-                            abs_path.clone()
-                        };
-                        if out_path.to_string_lossy().ends_with('/') {
-                            fs_err::tokio::create_dir_all(&out_path).await?;
-                            continue;
+                    resolution => {
+                        if resolution == Some(&UntrackedFileResolution::Overwrite) {
+                            fs.backup_untracked(&relative_path).await?;
                         }
-                        if let Some(parent) = out_path.parent() {
-                            fs_err::tokio::create_dir_all(parent).await?;
-                        }
-                        let mut reader = zip.reader_without_entry(index).await?;
-                        let mut writer = fs_err::tokio::File::create(&out_path)
-                            .await
-                            .map(|f| f.compat_write())?;
-                        futures::io::copy(&mut reader, &mut writer).await?;
+                        let hashed = file.hash().await?;
+                        fs.place_file(&file).await?;
+                        hashed
+                    }
+                };
+                files.push(ReceiptFile {
+                    path: file.path,
+                    scope: resource.scope.as_deref().cloned(),
+                    size,
+                    sha256,
+                });
+            }
+        }
+
+        let receipt = Receipt {
+            id: manifest.id.clone(),
+            version: manifest.as_version(),
+            files,
+        };
+        fs.write_receipt(&receipt).await?;
+
+        Ok(receipt.files)
+    }
+}
+
+/// インストールするファイルの取り出し元。
+enum Sources<'a> {
+    /// `perform`でダウンロードしたファイル。`{このディレクトリ}/{マニフェストのID}`にある。
+    Downloads(&'a std::path::Path),
+    /// ドライランで使う、キャッシュから分かるバンドルの中身とファイルの大きさ。
+    Cached {
+        listings: &'a BundleListings,
+        cache: &'a DownloadCache,
+    },
+}
+
+/// ダウンロードしたファイルを`staging_dir`に取り出し、リソースごとに配置するファイルを返す。
+/// `kept`のリソースは空になる。
+async fn stage_resources(
+    manifest: &crate::models::Manifest,
+    kept: &KeptResources,
+    temp_dir: &std::path::Path,
+    staging_dir: &std::path::Path,
+) -> anyhow::Result<Vec<Vec<StagedFile>>> {
+    let mut extractors = std::collections::HashMap::<
+        String,
+        (Box<dyn crate::archive::Extractor>, Vec<String>),
+    >::new();
+    let mut bundle_targets =
+        std::collections::HashMap::<String, crate::archive::ExtractTargets>::new();
+    let mut hash_checks = vec![];
+    let needed = needed_bundles(manifest, kept);
+    for name in nested_bundle_order(manifest)? {
+        if needed.contains(&name) {
+            extract_nested_bundle(manifest, &name, temp_dir).await?;
+        }
+    }
+    for resource in &manifest.resources {
+        if kept.contains_key(&resource.destination) {
+            continue;
+        }
+        let staged_path = staging_dir.join(resource.destination.as_relative_path());
+        match resource.source.scheme() {
+            "http" | "https" => {
+                let temp_file = temp_dir.join(url_to_file_name(&resource.source));
+                if !temp_file.exists() {
+                    return Err(anyhow::anyhow!(
+                        "Temporary file not found: {}",
+                        temp_file.display()
+                    ));
+                }
+                if let Some(parent) = staged_path.parent() {
+                    fs_err::tokio::create_dir_all(parent).await?;
+                }
+                fs_err::tokio::rename(&temp_file, &staged_path).await?;
+            }
+            "bundle" => {
+                let bundle_name = bundle_name(&resource.source)?.to_string();
+                if !extractors.contains_key(&bundle_name) {
+                    let opened = open_bundle(manifest, &bundle_name, temp_dir).await?;
+                    extractors.insert(bundle_name.clone(), opened);
+                }
+                let (_, names) = &extractors[&bundle_name];
+                let targets = bundle_targets.entry(bundle_name).or_default();
+                for (name, destination) in bundle_entries(resource, names)? {
+                    let out_path = staging_dir.join(destination.as_relative_path());
+                    if let Some(expected) = &resource.sha256 {
+                        hash_checks.push((&resource.source, out_path.clone(), expected));
+                    }
+                    targets.entry(name).or_default().push(out_path);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    for (bundle_name, targets) in &bundle_targets {
+        let (extractor, _) = extractors.get_mut(bundle_name).unwrap();
+        extractor.extract(targets).await?;
+    }
+    for (source, path, expected) in hash_checks {
+        let (_, actual) = hash_file(&path).await?;
+        verify_sha256(source.as_str(), expected, &actual)?;
+    }
+
+    let mut staged = vec![];
+    for resource in &manifest.resources {
+        if kept.contains_key(&resource.destination) {
+            staged.push(vec![]);
+            continue;
+        }
+        let staged_path = staging_dir.join(resource.destination.as_relative_path());
+        staged.push(
+            files_with_destinations(&staged_path, &resource.destination)
+                .await?
+                .into_iter()
+                .map(|(staged_file, path)| StagedFile {
+                    staged: Some(staged_file),
+                    path,
+                    size: None,
+                    sha256: None,
+                })
+                .collect(),
+        );
+    }
+    Ok(staged)
+}
+
+/// ファイルを取り出さずに、リソースごとに配置するファイルを`listings`と`cache`から分かる範囲で返す。
+/// `kept`のリソースは空になる。
+async fn preview_resources(
+    manifest: &crate::models::Manifest,
+    kept: &KeptResources,
+    listings: &BundleListings,
+    cache: &DownloadCache,
+) -> anyhow::Result<Vec<Vec<StagedFile>>> {
+    let mut staged = vec![];
+    for resource in &manifest.resources {
+        if kept.contains_key(&resource.destination) {
+            staged.push(vec![]);
+            continue;
+        }
+        let files = match resource.source.scheme() {
+            "http" | "https" => {
+                let key = match &resource.sha256 {
+                    Some(sha256) => CacheKey::Sha256(sha256.clone()),
+                    None => CacheKey::Url(url::Url::clone(&resource.source)),
+                };
+                vec![StagedFile {
+                    staged: None,
+                    path: resource.destination.clone(),
+                    size: cache.size(&key).await?,
+                    sha256: resource.sha256.clone(),
+                }]
+            }
+            "bundle" => {
+                let names = listings.get(&(
+                    manifest.id.clone(),
+                    bundle_name(&resource.source)?.to_string(),
+                ));
+                match names {
+                    Some(names) => bundle_entries(resource, names)?
+                        .into_iter()
+                        .map(|(_, path)| StagedFile {
+                            staged: None,
+                            path,
+                            size: None,
+                            sha256: resource.sha256.clone(),
+                        })
+                        .collect(),
+                    // NOTE: 中身が分からないバンドルは、配置先を1つのファイルとして扱う
+                    None => vec![StagedFile {
+                        staged: None,
+                        path: resource.destination.clone(),
+                        size: None,
+                        sha256: None,
+                    }],
+                }
+            }
+            _ => unreachable!(),
+        };
+        staged.push(files);
+    }
+    Ok(staged)
+}
+
+/// 更新時や修復時にそのまま残すリソースの、配置先とレシートの記録。
+type KeptResources =
+    std::collections::HashMap<crate::models::DataDirRelativePath, Vec<ReceiptFile>>;
+
+/// 更新前にインストールされていたもの。新規インストールの場合は空で、修復の場合は`kept`のみ。
+#[derive(Debug, Default)]
+struct PreviousInstall {
+    /// インストールされていたファイルの、データディレクトリからの相対パス。
+    paths: std::collections::HashSet<std::path::PathBuf>,
+    kept: KeptResources,
+}
+
+/// `kept`以外のリソースのために必要なバンドル。他のバンドル内のバンドルを使う場合は、その外側も含む。
+fn needed_bundles(
+    manifest: &crate::models::Manifest,
+    kept: &KeptResources,
+) -> std::collections::HashSet<String> {
+    let mut needed = manifest
+        .resources
+        .iter()
+        .filter(|resource| {
+            resource.source.scheme() == "bundle" && !kept.contains_key(&resource.destination)
+        })
+        .filter_map(|resource| bundle_name(&resource.source).ok())
+        .map(str::to_owned)
+        .collect::<std::collections::HashSet<_>>();
+    let mut pending = needed.iter().cloned().collect::<Vec<_>>();
+    while let Some(name) = pending.pop() {
+        let Ok(bundle) = find_bundle(manifest, &name) else {
+            continue;
+        };
+        if bundle.url().scheme() != "bundle" {
+            continue;
+        }
+        if let Ok(outer) = bundle_name(bundle.url()) {
+            if needed.insert(outer.to_owned()) {
+                pending.push(outer.to_owned());
+            }
+        }
+    }
+    needed
+}
+
+/// ダウンロードする1つのファイル。
+struct DownloadJob {
+    manifest_id: crate::models::ManifestId,
+    file: usize,
+    num_files: usize,
+    source: url::Url,
+    sha256: Option<String>,
+    destination: std::path::PathBuf,
+    /// 必要な部分だけをダウンロードできるバンドルの場合、その情報。
+    partial: Option<PartialBundle>,
+}
+
+impl DownloadJob {
+    fn for_manifest(
+        manifest: &crate::models::Manifest,
+        temp_dir: &std::path::Path,
+        kept: &KeptResources,
+    ) -> Vec<Self> {
+        let needed = needed_bundles(manifest, kept);
+        let files_to_download = manifest
+            .resources
+            .iter()
+            .filter(|c| c.source.scheme() == "http" || c.source.scheme() == "https")
+            .filter(|c| !kept.contains_key(&c.destination))
+            .map(|c| {
+                (
+                    url::Url::clone(&c.source),
+                    c.sha256.clone(),
+                    temp_dir.join(url_to_file_name(&c.source)),
+                    None,
+                )
+            })
+            .chain(manifest.bundles.iter().flat_map(|c| {
+                // NOTE: 他のバンドル内のバンドルはインストール時に取り出す
+                c.iter()
+                    .filter(|(name, bundle)| {
+                        bundle.url().scheme() != "bundle" && needed.contains(name.as_str())
+                    })
+                    .map(|(name, bundle)| {
+                        (
+                            url::Url::clone(bundle.url()),
+                            bundle.sha256().map(str::to_owned),
+                            temp_dir.join(&**name),
+                            PartialBundle::new(manifest, name, bundle, kept),
+                        )
+                    })
+            }))
+            .collect::<Vec<_>>();
+        let num_files = files_to_download.len();
+        files_to_download
+            .into_iter()
+            .enumerate()
+            .map(|(i, (source, sha256, destination, partial))| Self {
+                manifest_id: manifest.id.clone(),
+                file: i,
+                num_files,
+                source,
+                sha256,
+                destination,
+                partial,
+            })
+            .collect()
+    }
+
+    async fn download(
+        &self,
+        options: &InstallOptions<'_>,
+        ch: &tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
+    ) -> anyhow::Result<()> {
+        let name = self.destination.to_string_lossy().to_string();
+        ch.send((
+            self.manifest_id.clone(),
+            InstallProgress::Download {
+                file: self.file,
+                num_files: self.num_files,
+                name: name.clone(),
+                bytes: 0,
+                total_bytes: None,
+                resumed_from: 0,
+            },
+        ))?;
+
+        assert!(self.source.scheme() == "http" || self.source.scheme() == "https");
+
+        let cache_key = match &self.sha256 {
+            Some(sha256) => CacheKey::Sha256(sha256.clone()),
+            None => CacheKey::Url(self.source.clone()),
+        };
+        let mut etag = None;
+        if let Some(cache) = options.cache {
+            match &self.sha256 {
+                // NOTE: ハッシュ値をキーにしたキャッシュは、確認してから追加しているのでそのまま使う
+                Some(_) => {
+                    if cache.get(&cache_key, &self.destination).await? {
+                        let size = fs_err::tokio::metadata(&self.destination).await?.len();
+                        self.send_cached(size, &name, ch)?;
+                        return Ok(());
                     }
                 }
-                _ => unreachable!(),
+                None => etag = cache.etag(&cache_key).await?,
             }
         }
 
-        ch.send((manifest.id.clone(), InstallProgress::Complete))?;
+        let client = reqwest::Client::new();
+        if let (None, Some(partial)) = (&etag, &self.partial) {
+            match crate::zip_range::download_entries(
+                &client,
+                &self.source,
+                &self.destination,
+                partial.filename_encoding,
+                |names| partial.wanted(names),
+                |progress| self.send_progress(&name, progress, ch),
+            )
+            .await
+            {
+                // NOTE: 一部だけのファイルはキャッシュしない
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(e) => log::warn!(
+                    "Partial download of {} failed, downloading the whole file: {e:?}",
+                    self.source
+                ),
+            }
+        }
+        let download_options = DownloadOptions::default();
+        let mut outcome = download(
+            &client,
+            &self.source,
+            &self.destination,
+            etag.as_deref(),
+            &download_options,
+            |progress| self.send_progress(&name, progress, ch),
+        )
+        .await?;
+        if outcome == DownloadOutcome::NotModified {
+            if let Some(cache) = options.cache {
+                if cache.get(&cache_key, &self.destination).await? {
+                    let size = fs_err::tokio::metadata(&self.destination).await?.len();
+                    self.send_cached(size, &name, ch)?;
+                    return Ok(());
+                }
+            }
+            // キャッシュが消えていた場合はもう一度ダウンロードする
+            outcome = download(
+                &client,
+                &self.source,
+                &self.destination,
+                None,
+                &download_options,
+                |progress| self.send_progress(&name, progress, ch),
+            )
+            .await?;
+        }
+        let DownloadOutcome::Downloaded { etag, sha256 } = outcome else {
+            anyhow::bail!("Unexpected 304 Not Modified: {}", self.source);
+        };
+
+        if let Some(expected) = &self.sha256 {
+            verify_sha256(self.source.as_str(), expected, &sha256)?;
+        }
+
+        if let Some(cache) = options.cache {
+            if self.sha256.is_some() || etag.is_some() {
+                if let Err(e) = cache
+                    .insert(&cache_key, &self.destination, etag, options.cache_max_size)
+                    .await
+                {
+                    log::warn!("Failed to cache {}: {e:?}", self.source);
+                }
+            }
+        }
         Ok(())
     }
+
+    fn send_progress(
+        &self,
+        name: &str,
+        progress: DownloadProgress,
+        ch: &tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
+    ) -> anyhow::Result<()> {
+        ch.send((
+            self.manifest_id.clone(),
+            InstallProgress::Download {
+                file: self.file,
+                num_files: self.num_files,
+                name: name.to_owned(),
+                bytes: progress.bytes,
+                total_bytes: progress.total_bytes,
+                resumed_from: progress.resumed_from,
+            },
+        ))?;
+        Ok(())
+    }
+
+    fn send_cached(
+        &self,
+        size: u64,
+        name: &str,
+        ch: &tauri::ipc::Channel<(crate::models::ManifestId, InstallProgress)>,
+    ) -> anyhow::Result<()> {
+        log::info!("Using cached file for {}", self.source);
+        ch.send((
+            self.manifest_id.clone(),
+            InstallProgress::Download {
+                file: self.file,
+                num_files: self.num_files,
+                name: name.to_owned(),
+                bytes: size,
+                total_bytes: Some(size),
+                resumed_from: 0,
+            },
+        ))?;
+        Ok(())
+    }
+}
+
+/// 中身の一部だけをダウンロードできるバンドル。
+struct PartialBundle {
+    /// このバンドルを使うリソース。
+    resources: Vec<crate::models::ManifestResourcesItem>,
+    /// このバンドルの中にある、他のバンドルのパス。
+    nested: Vec<String>,
+    filename_encoding: Option<&'static encoding_rs::Encoding>,
+}
+
+impl PartialBundle {
+    fn new(
+        manifest: &crate::models::Manifest,
+        name: &str,
+        bundle: &crate::models::Bundle,
+        kept: &KeptResources,
+    ) -> Option<Self> {
+        // NOTE: SHA256ハッシュ値はファイル全体のものなので、一部だけでは検証できない
+        if bundle.sha256().is_some() {
+            return None;
+        }
+        let filename_encoding = crate::archive::OpenOptions::for_bundle(bundle)
+            .ok()?
+            .filename_encoding;
+        let uses_bundle = |url: &url::Url| {
+            url.scheme() == "bundle" && bundle_name(url).is_ok_and(|outer| outer == name)
+        };
+        let resources = manifest
+            .resources
+            .iter()
+            .filter(|resource| {
+                uses_bundle(&resource.source) && !kept.contains_key(&resource.destination)
+            })
+            .cloned()
+            .collect();
+        let nested = manifest
+            .bundles
+            .iter()
+            .flatten()
+            .filter(|(_, nested)| uses_bundle(nested.url()))
+            .map(
+                |(_, nested)| match BundleSelector::new(nested.url(), false) {
+                    Ok(BundleSelector::File(path)) => Some(path),
+                    _ => None,
+                },
+            )
+            .collect::<Option<_>>()?;
+        Some(Self {
+            resources,
+            nested,
+            filename_encoding,
+        })
+    }
+
+    /// バンドルの中身`names`のうち、インストールに必要なもの。
+    fn wanted(&self, names: &[String]) -> anyhow::Result<std::collections::HashSet<String>> {
+        let mut wanted = self
+            .nested
+            .iter()
+            .cloned()
+            .collect::<std::collections::HashSet<_>>();
+        for resource in &self.resources {
+            wanted.extend(
+                bundle_entries(resource, names)?
+                    .into_iter()
+                    .map(|(name, _)| name),
+            );
+        }
+        Ok(wanted)
+    }
+}
+
+/// インストール前に分かっている、バンドルの中身の一覧。キーはマニフェストのIDとバンドル名。
+pub type BundleListings =
+    std::collections::HashMap<(crate::models::ManifestId, String), Vec<String>>;
+
+fn bundle_name(source: &url::Url) -> anyhow::Result<&str> {
+    source
+        .host_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid bundle URL: {source}"))
+}
+
+/// `bundle://`で他のバンドルの中を指すバンドルを、取り出す順番に並べる。
+fn nested_bundle_order(manifest: &crate::models::Manifest) -> anyhow::Result<Vec<String>> {
+    let bundles = manifest
+        .bundles
+        .iter()
+        .flatten()
+        .map(|(name, bundle)| (name.as_str(), bundle))
+        .collect::<std::collections::HashMap<_, _>>();
+    let mut nested = Vec::new();
+    for (name, bundle) in manifest.bundles.iter().flatten() {
+        // NOTE: 外側のバンドルは1つだけなので、辿ると一本道になる
+        let mut chain = vec![name.as_str()];
+        let mut current = bundle;
+        while current.url().scheme() == "bundle" {
+            let outer = bundle_name(current.url())?;
+            let is_cycle = chain.contains(&outer);
+            chain.push(outer);
+            if is_cycle {
+                anyhow::bail!("#bundle_cycle[{}]", serde_json::to_string(&chain)?);
+            }
+            current = bundles
+                .get(outer)
+                .ok_or_else(|| anyhow::anyhow!("Bundle not found: {}", current.url()))?;
+        }
+        if chain.len() > 1 {
+            nested.push((chain.len(), name.to_string()));
+        }
+    }
+    nested.sort();
+    Ok(nested.into_iter().map(|(_, name)| name).collect())
+}
+
+fn find_bundle<'a>(
+    manifest: &'a crate::models::Manifest,
+    name: &str,
+) -> anyhow::Result<&'a crate::models::Bundle> {
+    manifest
+        .bundles
+        .iter()
+        .flatten()
+        .find(|(bundle_name, _)| bundle_name.as_str() == name)
+        .map(|(_, bundle)| bundle)
+        .ok_or_else(|| anyhow::anyhow!("Bundle not found: {name}"))
+}
+
+/// ダウンロード済み、または取り出し済みのバンドルを開き、中身の一覧と共に返す。
+async fn open_bundle(
+    manifest: &crate::models::Manifest,
+    name: &str,
+    temp_dir: &std::path::Path,
+) -> anyhow::Result<(Box<dyn crate::archive::Extractor>, Vec<String>)> {
+    let bundle = find_bundle(manifest, name)?;
+    let bundle_path = temp_dir.join(name);
+    if !bundle_path.exists() {
+        return Err(anyhow::anyhow!(
+            "Temporary bundle file not found: {}",
+            bundle_path.display()
+        ));
+    }
+    let mut extractor = crate::archive::open(
+        &bundle_path,
+        &crate::archive::OpenOptions::for_bundle(bundle)?,
+    )
+    .await?;
+    let names = extractor.list().await?;
+    Ok((extractor, names))
+}
+
+/// 他のバンドルの中にあるバンドル`name`を取り出す。外側のバンドルは取り出し済みである必要がある。
+async fn extract_nested_bundle(
+    manifest: &crate::models::Manifest,
+    name: &str,
+    temp_dir: &std::path::Path,
+) -> anyhow::Result<()> {
+    let bundle = find_bundle(manifest, name)?;
+    let url = bundle.url();
+    let BundleSelector::File(path) = BundleSelector::new(url, false)? else {
+        anyhow::bail!("Nested bundle must point to a single file: {url}");
+    };
+    let (mut extractor, names) = open_bundle(manifest, bundle_name(url)?, temp_dir).await?;
+    if !names.contains(&path) {
+        anyhow::bail!("File not found in bundle: {}", url.path());
+    }
+    let destination = temp_dir.join(name);
+    extractor
+        .extract(&crate::archive::ExtractTargets::from([(
+            path,
+            vec![destination.clone()],
+        )]))
+        .await?;
+    if let Some(expected) = bundle.sha256() {
+        let (_, actual) = hash_file(&destination).await?;
+        verify_sha256(url.as_str(), expected, &actual)?;
+    }
+    Ok(())
+}
+
+/// `bundle://`のパスの指定方法。
+enum BundleSelector {
+    /// 1つのファイル。
+    File(String),
+    /// ディレクトリ以下の全てのファイル。
+    Directory(String),
+    /// globパターンに一致する全てのファイル。`base`はパターンを含む最初の階層より前の部分。
+    Glob {
+        base: String,
+        matcher: globset::GlobMatcher,
+    },
+}
+
+impl BundleSelector {
+    /// `glob`が`true`の場合のみ、パスをglobパターンとして扱う。
+    ///
+    /// NOTE: `[v2]`のように、globの記号を含むファイル名はよくあるので、明示された場合以外はそのままのパスとして扱う
+    fn new(source: &url::Url, glob: bool) -> anyhow::Result<Self> {
+        let path = percent_encoding::percent_decode_str(source.path()).decode_utf8()?;
+        // NOTE: URLのパスは`/`から始まるが、アーカイブ内のパスは`/`から始まらない
+        let path = path.trim_start_matches('/');
+        let is_glob = |segment: &str| segment.contains(['*', '?', '[', '{']);
+        if glob && is_glob(path) {
+            let base = path
+                .split('/')
+                .take_while(|segment| !is_glob(segment))
+                .map(|segment| format!("{segment}/"))
+                .collect();
+            let matcher = globset::GlobBuilder::new(path)
+                .literal_separator(true)
+                .build()?
+                .compile_matcher();
+            Ok(Self::Glob { base, matcher })
+        } else if path.is_empty() || path.ends_with('/') {
+            Ok(Self::Directory(path.to_owned()))
+        } else {
+            Ok(Self::File(path.to_owned()))
+        }
+    }
+
+    /// `name`がこの指定に一致する場合、取り出したファイルを置く相対パスを返す。
+    fn relative_path<'a>(&self, name: &'a str) -> Option<&'a str> {
+        match self {
+            Self::File(path) => (name == path).then_some(""),
+            Self::Directory(path) => name.strip_prefix(path.as_str()),
+            Self::Glob { base, matcher } => {
+                if matcher.is_match(name) {
+                    name.strip_prefix(base.as_str())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// バンドルの中身`names`のうち、`resource`で取り出すものとその配置先。
+pub fn bundle_entries(
+    resource: &crate::models::ManifestResourcesItem,
+    names: &[String],
+) -> anyhow::Result<Vec<(String, crate::models::DataDirRelativePath)>> {
+    let selector = BundleSelector::new(&resource.source, resource.glob.unwrap_or(false))?;
+    let wants_directory = !matches!(selector, BundleSelector::File(_));
+    if wants_directory != resource.destination.is_directory() {
+        return Err(anyhow::anyhow!(
+            "Resource source and destination must both be files or both be directories: source={}, destination={}",
+            resource.source,
+            resource.destination
+        ));
+    }
+    if wants_directory && resource.sha256.is_some() {
+        return Err(anyhow::anyhow!(
+            "sha256 cannot be specified for directory resources: {}",
+            resource.source
+        ));
+    }
+    if !wants_directory && resource.strip_components.is_some() {
+        return Err(anyhow::anyhow!(
+            "strip_components cannot be specified for single file resources: {}",
+            resource.source
+        ));
+    }
+    let strip_components = resource.strip_components.map_or(0, |n| *n as usize);
+
+    let mut entries = Vec::new();
+    for name in names {
+        let Some(relative_path) = selector.relative_path(name) else {
+            continue;
+        };
+        if !wants_directory {
+            entries.push((name.clone(), resource.destination.clone()));
+            continue;
+        }
+        let relative_path = crate::archive::sanitize_entry_path(relative_path)?;
+        let mut segments = relative_path.split('/');
+        if segments.by_ref().take(strip_components).count() < strip_components {
+            continue;
+        }
+        let relative_path = segments.collect::<Vec<_>>().join("/");
+        if relative_path.is_empty() {
+            continue;
+        }
+        entries.push((
+            name.clone(),
+            crate::models::DataDirRelativePath(resource.destination.join(&relative_path)),
+        ));
+    }
+    if entries.is_empty() {
+        return Err(anyhow::anyhow!(
+            "File not found in bundle: {}",
+            resource.source.path()
+        ));
+    }
+    if !wants_directory && entries.len() > 1 {
+        return Err(anyhow::anyhow!(
+            "Multiple files found in bundle for single file resource: {}",
+            resource.source.path()
+        ));
+    }
+    Ok(entries)
+}
+
+/// キャッシュにあるバンドルの中身の一覧を作る。キャッシュに無いバンドルは含まれない。
+pub async fn cached_bundle_listings(
+    root: &std::path::Path,
+    manifests: &[crate::models::Manifest],
+    cache: &DownloadCache,
+) -> anyhow::Result<BundleListings> {
+    let temp_dir = root.join(DATA_DIR).join(TEMP_DIR).join("listings");
+    let mut listings = BundleListings::new();
+    for manifest in manifests {
+        for (name, bundle) in manifest.bundles.iter().flatten() {
+            if bundle.url().scheme() == "bundle" {
+                continue;
+            }
+            let key = match bundle.sha256() {
+                Some(sha256) => CacheKey::Sha256(sha256.to_owned()),
+                None => CacheKey::Url(url::Url::clone(bundle.url())),
+            };
+            fs_err::tokio::create_dir_all(&temp_dir).await?;
+            let temp_file = temp_dir.join(uuid::Uuid::now_v7().to_string());
+            if !cache.get(&key, &temp_file).await? {
+                continue;
+            }
+            let names = async {
+                let options = crate::archive::OpenOptions::for_bundle(bundle)?;
+                crate::archive::open(&temp_file, &options)
+                    .await?
+                    .list()
+                    .await
+            }
+            .await;
+            match names {
+                Ok(names) => {
+                    listings.insert((manifest.id.clone(), name.to_string()), names);
+                }
+                Err(e) => log::warn!("Failed to list bundle {key}: {e}"),
+            }
+        }
+    }
+    if temp_dir.exists() {
+        fs_err::tokio::remove_dir_all(&temp_dir).await?;
+    }
+    Ok(listings)
+}
+
+/// `path`以下のファイルと、それぞれを`destination`に置いたときのパスの一覧。
+pub async fn files_with_destinations(
+    path: &std::path::Path,
+    destination: &crate::models::DataDirRelativePath,
+) -> anyhow::Result<Vec<(std::path::PathBuf, crate::models::DataDirRelativePath)>> {
+    let Ok(metadata) = fs_err::tokio::metadata(path).await else {
+        return Ok(vec![]);
+    };
+    if metadata.is_file() {
+        return Ok(vec![(path.to_path_buf(), destination.clone())]);
+    }
+
+    let mut files = vec![];
+    let mut walk = async_walkdir::WalkDir::new(path);
+    while let Some(entry) = walk.next().await {
+        let entry = entry?;
+        if !entry.file_type().await?.is_file() {
+            continue;
+        }
+        let file = entry.path();
+        let relative_path = file
+            .strip_prefix(path)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((
+            file,
+            crate::models::DataDirRelativePath(destination.join(&relative_path)),
+        ));
+    }
+    Ok(files)
+}
+
+pub fn list_files_blocking(path: &std::path::Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let Ok(metadata) = fs_err::metadata(path) else {
+        return Ok(vec![]);
+    };
+    if !metadata.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = vec![];
+    for entry in fs_err::read_dir(path)? {
+        files.extend(list_files_blocking(&entry?.path())?);
+    }
+    Ok(files)
+}
+
+/// 上書きされる管理外のファイルを`au2ec/backups`にコピーする。
+pub async fn backup_untracked_file(
+    root: &std::path::Path,
+    relative_path: &std::path::Path,
+) -> anyhow::Result<()> {
+    let backup_path = root
+        .join(DATA_DIR)
+        .join("backups")
+        .join(chrono::Local::now().format("%Y%m%d%H%M%S").to_string())
+        .join(relative_path);
+    if let Some(parent) = backup_path.parent() {
+        fs_err::tokio::create_dir_all(parent).await?;
+    }
+    fs_err::tokio::copy(root.join(relative_path), &backup_path).await?;
+    Ok(())
+}
+
+pub async fn hash_file(path: &std::path::Path) -> std::io::Result<(u64, String)> {
+    let mut file = fs_err::tokio::File::open(path).await?.compat();
+    let mut hasher = sha2::Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        size += read as u64;
+    }
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
+
+fn verify_sha256(name: &str, expected: &str, actual: &str) -> Result<(), InstallError> {
+    if expected.eq_ignore_ascii_case(actual) {
+        Ok(())
+    } else {
+        Err(InstallError::HashMismatch {
+            name: name.to_string(),
+            expected: expected.to_ascii_lowercase(),
+            actual: actual.to_string(),
+        })
+    }
 }
 
 fn url_to_file_name(name: &url::Url) -> String {
@@ -677,4 +2007,497 @@ mod tests {
         assert!(InstallPlan::check_path_conflict(path2, path4));
         assert!(InstallPlan::check_path_conflict(path4, path2));
     }
+
+    fn manifest(id: &str, files: serde_json::Value) -> crate::models::Manifest {
+        let mut manifest = serde_json::json!({
+            "manifest_version": 1,
+            "id": id,
+            "name": id,
+            "summary": "",
+            "version": "1.0.0",
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "resources": [],
+        });
+        for (key, value) in files.as_object().unwrap() {
+            manifest[key] = value.clone();
+        }
+        serde_json::from_value(manifest).unwrap()
+    }
+
+    async fn plan(
+        existing: &[crate::models::Manifest],
+        desired: &[crate::models::Manifest],
+    ) -> Result<InstallPlan, PlanError> {
+        InstallPlan::plan(
+            std::path::Path::new("/nonexistent"),
+            existing,
+            desired,
+            &[],
+            &Default::default(),
+            &Default::default(),
+        )
+        .await
+    }
+
+    /// `installed`のマニフェストをインストールした状態の一時ディレクトリを作る。
+    /// それぞれ`(パス, 内容)`のファイルを書き込み、レシートに記録します。
+    async fn fixture(
+        installed: &[(&crate::models::Manifest, &[(&str, &str)])],
+    ) -> tempfile::TempDir {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (manifest, files) in installed {
+            let mut receipt = Receipt {
+                id: manifest.id.clone(),
+                version: manifest.as_version(),
+                files: vec![],
+            };
+            for (path, content) in *files {
+                let path: crate::models::DataDirRelativePath =
+                    serde_json::from_value(serde_json::json!(path)).unwrap();
+                let abs_path = path.to_absolute_path(temp_dir.path());
+                fs_err::tokio::create_dir_all(abs_path.parent().unwrap())
+                    .await
+                    .unwrap();
+                fs_err::tokio::write(&abs_path, content).await.unwrap();
+                receipt.files.push(ReceiptFile {
+                    scope: manifest
+                        .resources
+                        .iter()
+                        .find(|resource| resource.destination == path)
+                        .and_then(|resource| resource.scope.as_deref().cloned()),
+                    size: content.len() as u64,
+                    sha256: format!("{:x}", sha2::Sha256::digest(content.as_bytes())),
+                    path,
+                });
+            }
+            let receipt_path = temp_dir.path().join(Receipt::relative_path(&manifest.id));
+            fs_err::tokio::create_dir_all(receipt_path.parent().unwrap())
+                .await
+                .unwrap();
+            fs_err::tokio::write(&receipt_path, serde_json::to_vec(&receipt).unwrap())
+                .await
+                .unwrap();
+        }
+        temp_dir
+    }
+
+    #[rstest::rstest]
+    #[case::same_path(
+        serde_json::json!({ "resources": [{ "source": "https://example.com/a.dll", "destination": "$data/common.dll" }] }),
+        serde_json::json!({ "resources": [{ "source": "https://example.com/b.dll", "destination": "$data/common.dll" }] }),
+        true
+    )]
+    #[case::same_scope(
+        serde_json::json!({ "resources": [{ "source": "https://example.com/a.dll", "destination": "$data/common.dll", "scope": "common" }] }),
+        serde_json::json!({ "resources": [{ "source": "https://example.com/b.dll", "destination": "$data/common.dll", "scope": "common" }] }),
+        false
+    )]
+    #[case::different_scope(
+        serde_json::json!({ "resources": [{ "source": "https://example.com/a.dll", "destination": "$data/common.dll", "scope": "a" }] }),
+        serde_json::json!({ "resources": [{ "source": "https://example.com/b.dll", "destination": "$data/common.dll", "scope": "b" }] }),
+        true
+    )]
+    #[case::same_scope_different_usage(
+        serde_json::json!({ "resources": [{ "source": "https://example.com/a.dll", "destination": "$data/common.dll", "scope": "common" }] }),
+        serde_json::json!({ "configurations": [{ "path": "$data/common.dll", "scope": "common" }] }),
+        true
+    )]
+    #[case::directory(
+        serde_json::json!({ "disposables": [{ "path": "$data/some_dir/" }] }),
+        serde_json::json!({ "resources": [{ "source": "https://example.com/b.txt", "destination": "$data/some_dir/my_file.txt" }] }),
+        true
+    )]
+    #[case::same_manifest_overlap(
+        serde_json::json!({
+            "resources": [{ "source": "https://example.com/a.auo2", "destination": "$plugin/a/a.auo2" }],
+            "configurations": [{ "path": "$plugin/a/" }],
+        }),
+        serde_json::json!({}),
+        false
+    )]
+    #[case::different_path(
+        serde_json::json!({ "resources": [{ "source": "https://example.com/a.auo2", "destination": "$plugin/a.auo2" }] }),
+        serde_json::json!({ "resources": [{ "source": "https://example.com/b.auo2", "destination": "$plugin/b.auo2" }] }),
+        false
+    )]
+    #[tokio::test]
+    async fn test_plan_conflict(
+        #[case] files_a: serde_json::Value,
+        #[case] files_b: serde_json::Value,
+        #[case] conflicts: bool,
+    ) {
+        let a = manifest("test-a", files_a);
+        let b = manifest("test-b", files_b);
+
+        // 新規インストール同士
+        let result = plan(&[], &[a.clone(), b.clone()]).await;
+        assert_eq!(matches!(result, Err(PlanError::Conflict(_))), conflicts);
+
+        // インストール済みのものとの競合
+        let result = plan(std::slice::from_ref(&a), std::slice::from_ref(&b)).await;
+        assert_eq!(matches!(result, Err(PlanError::Conflict(_))), conflicts);
+
+        // 更新するものとの競合
+        let result = plan(std::slice::from_ref(&a), &[a.clone(), b.clone()]).await;
+        assert_eq!(matches!(result, Err(PlanError::Conflict(_))), conflicts);
+    }
+
+    #[rstest::rstest]
+    #[case::file(
+        serde_json::json!({ "source": "bundle://b/a/x.anm2", "destination": "$script/x.anm2" }),
+        Some(vec![("a/x.anm2", "$script/x.anm2")])
+    )]
+    #[case::directory(
+        serde_json::json!({ "source": "bundle://b/a/", "destination": "$script/a/" }),
+        Some(vec![("a/x.anm2", "$script/a/x.anm2"), ("a/sub/y.anm2", "$script/a/sub/y.anm2"), ("a/z.txt", "$script/a/z.txt")])
+    )]
+    #[case::glob(
+        serde_json::json!({ "source": "bundle://b/a/*.anm2", "destination": "$script/pack/", "glob": true }),
+        Some(vec![("a/x.anm2", "$script/pack/x.anm2")])
+    )]
+    #[case::recursive_glob(
+        serde_json::json!({ "source": "bundle://b/**/*.anm2", "destination": "$script/pack/", "glob": true }),
+        Some(vec![("a/x.anm2", "$script/pack/a/x.anm2"), ("a/sub/y.anm2", "$script/pack/a/sub/y.anm2"), ("スクリプト/表示.anm2", "$script/pack/スクリプト/表示.anm2")])
+    )]
+    #[case::strip_components(
+        serde_json::json!({ "source": "bundle://b/", "destination": "$script/pack/", "strip_components": 1 }),
+        Some(vec![("a/x.anm2", "$script/pack/x.anm2"), ("a/sub/y.anm2", "$script/pack/sub/y.anm2"), ("a/z.txt", "$script/pack/z.txt"), ("スクリプト/表示.anm2", "$script/pack/表示.anm2")])
+    )]
+    #[case::percent_encoded(
+        serde_json::json!({ "source": "bundle://b/スクリプト/", "destination": "$script/pack/" }),
+        Some(vec![("スクリプト/表示.anm2", "$script/pack/表示.anm2")])
+    )]
+    #[case::not_found(
+        serde_json::json!({ "source": "bundle://b/*.dll", "destination": "$plugin/pack/", "glob": true }),
+        None
+    )]
+    #[case::glob_to_file(
+        serde_json::json!({ "source": "bundle://b/a/*.anm2", "destination": "$script/x.anm2", "glob": true }),
+        None
+    )]
+    fn test_bundle_entries(
+        #[case] resource: serde_json::Value,
+        #[case] expected: Option<Vec<(&str, &str)>>,
+    ) {
+        let names = [
+            "a/x.anm2",
+            "a/sub/y.anm2",
+            "a/z.txt",
+            "スクリプト/表示.anm2",
+            "top.txt",
+        ]
+        .map(str::to_owned);
+        let resource: crate::models::ManifestResourcesItem =
+            serde_json::from_value(resource).unwrap();
+        let entries = bundle_entries(&resource, &names).ok().map(|entries| {
+            entries
+                .into_iter()
+                .map(|(name, destination)| (name, destination.to_string()))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            entries,
+            expected.map(|expected| expected
+                .into_iter()
+                .map(|(name, destination)| (name.to_owned(), destination.to_owned()))
+                .collect())
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::directory("bundle://b/[v2]/", "$script/pack/", "$script/pack/x.anm2")]
+    #[case::file("bundle://b/[v2]/x.anm2", "$script/x.anm2", "$script/x.anm2")]
+    fn test_bundle_entries_literal_brackets(
+        #[case] source: &str,
+        #[case] destination: &str,
+        #[case] expected: &str,
+    ) {
+        let names = ["[v2]/x.anm2", "v/x.anm2"].map(str::to_owned);
+        let resource: crate::models::ManifestResourcesItem = serde_json::from_value(
+            serde_json::json!({ "source": source, "destination": destination }),
+        )
+        .unwrap();
+        let entries = bundle_entries(&resource, &names)
+            .unwrap()
+            .into_iter()
+            .map(|(name, destination)| (name, destination.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, [("[v2]/x.anm2".to_owned(), expected.to_owned())]);
+    }
+
+    #[rstest::rstest]
+    #[case::disjoint(&["b.anm2"], false)]
+    #[case::overlapping(&["a.anm2"], true)]
+    #[tokio::test]
+    async fn test_plan_bundle_listing(#[case] names_b: &[&str], #[case] conflicts: bool) {
+        let files = serde_json::json!({
+            "bundles": { "main": "https://example.com/bundle.zip" },
+            "resources": [{ "source": "bundle://main/", "destination": "$script/pack/" }],
+        });
+        let a = manifest("test-a", files.clone());
+        let b = manifest("test-b", files);
+        let listings = BundleListings::from([
+            ((a.id.clone(), "main".to_owned()), vec!["a.anm2".to_owned()]),
+            (
+                (b.id.clone(), "main".to_owned()),
+                names_b.iter().map(|name| name.to_string()).collect(),
+            ),
+        ]);
+        let result = InstallPlan::plan(
+            std::path::Path::new("/nonexistent"),
+            &[],
+            &[a, b],
+            &[],
+            &Default::default(),
+            &listings,
+        )
+        .await;
+        assert_eq!(matches!(result, Err(PlanError::Conflict(_))), conflicts);
+    }
+
+    #[tokio::test]
+    async fn test_plan_untracked_files_from_listing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs_err::tokio::create_dir_all(root.join("Script/pack"))
+            .await
+            .unwrap();
+        for name in ["a.anm2", "user.anm2"] {
+            fs_err::tokio::write(root.join("Script/pack").join(name), "")
+                .await
+                .unwrap();
+        }
+        let a = manifest(
+            "test-a",
+            serde_json::json!({
+                "bundles": { "main": "https://example.com/bundle.zip" },
+                "resources": [{ "source": "bundle://main/", "destination": "$script/pack/" }],
+            }),
+        );
+        let listings =
+            BundleListings::from([((a.id.clone(), "main".to_owned()), vec!["a.anm2".to_owned()])]);
+        let result = InstallPlan::plan(root, &[], &[a], &[], &Default::default(), &listings).await;
+        let Err(PlanError::UntrackedFiles(files)) = result else {
+            panic!("expected untracked files: {result:?}");
+        };
+        assert_eq!(
+            files.into_iter().map(|file| file.path).collect::<Vec<_>>(),
+            [std::path::PathBuf::from("Script/pack/a.anm2")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plan_keeps_existing() {
+        let a = manifest("test-a", serde_json::json!({}));
+        let b = manifest("test-b", serde_json::json!({}));
+        let plan = plan(std::slice::from_ref(&a), std::slice::from_ref(&b))
+            .await
+            .unwrap();
+        assert_eq!(plan.to_keep.len(), 1);
+        assert_eq!(plan.to_keep[0].id, a.id);
+        assert_eq!(plan.to_install.len(), 1);
+    }
+
+    #[rstest::rstest]
+    #[case::nested(
+        serde_json::json!({ "outer": "https://example.com/outer.zip", "inner": "bundle://middle/inner.zip", "middle": "bundle://outer/middle.zip" }),
+        Some(vec!["middle", "inner"])
+    )]
+    #[case::cycle(
+        serde_json::json!({ "a": "bundle://b/a.zip", "b": "bundle://a/b.zip" }),
+        None
+    )]
+    #[case::missing_outer(
+        serde_json::json!({ "inner": "bundle://outer/inner.zip" }),
+        None
+    )]
+    fn test_nested_bundle_order(
+        #[case] bundles: serde_json::Value,
+        #[case] expected: Option<Vec<&str>>,
+    ) {
+        let manifest = manifest("test-a", serde_json::json!({ "bundles": bundles }));
+        assert_eq!(
+            nested_bundle_order(&manifest).ok(),
+            expected.map(|expected| expected.into_iter().map(str::to_owned).collect())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_kept_resources() {
+        let sha256 = |content: &str| format!("{:x}", sha2::Sha256::digest(content.as_bytes()));
+        let manifest = manifest(
+            "test-a",
+            serde_json::json!({
+                "bundles": { "main": "https://example.com/bundle.zip" },
+                "resources": [
+                    { "source": "https://example.com/a.anm2", "destination": "$script/a.anm2", "sha256": sha256("a") },
+                    { "source": "https://example.com/b.anm2", "destination": "$script/b.anm2", "sha256": sha256("b") },
+                    { "source": "https://example.com/c.anm2", "destination": "$script/c.anm2" },
+                    { "source": "https://example.com/d.anm2", "destination": "$script/d.anm2", "sha256": sha256("new") },
+                    { "source": "bundle://main/", "destination": "$script/pack/" },
+                ],
+            }),
+        );
+        let temp_dir = fixture(&[(
+            &manifest,
+            &[
+                ("$script/a.anm2", "a"),
+                ("$script/b.anm2", "b"),
+                ("$script/c.anm2", "c"),
+                ("$script/d.anm2", "old"),
+                ("$script/pack/e.anm2", "e"),
+            ],
+        )])
+        .await;
+        let root = temp_dir.path();
+        // b.anm2は手動で書き換えられている
+        fs_err::tokio::write(root.join("Script/b.anm2"), "modified")
+            .await
+            .unwrap();
+
+        // 更新時は、ハッシュ値が変わらず書き換えられてもいないものだけを残す
+        let kept = InstallPlan::unchanged_resources(root, &manifest)
+            .await
+            .unwrap();
+        assert_eq!(
+            kept.keys().map(|path| path.to_string()).collect::<Vec<_>>(),
+            ["$script/a.anm2"]
+        );
+
+        // 修復時は、壊れたファイルを含むものだけをダウンロードし直す
+        let kept = InstallPlan::intact_resources(
+            root,
+            &manifest,
+            &[std::path::PathBuf::from("Script/a.anm2")],
+        )
+        .await
+        .unwrap();
+        let jobs = DownloadJob::for_manifest(&manifest, root, &kept);
+        assert_eq!(
+            jobs.iter()
+                .map(|job| job.source.as_str())
+                .collect::<Vec<_>>(),
+            ["https://example.com/a.anm2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exclude_shared_usages() {
+        let files = |id: &str| {
+            serde_json::json!({ "resources": [
+                { "source": "https://example.com/common.dll", "destination": "$data/common.dll", "scope": "common" },
+                { "source": format!("https://example.com/{id}.auo2"), "destination": format!("$plugin/{id}.auo2") },
+            ] })
+        };
+        let a = manifest("test-a", files("a"));
+        let b = manifest("test-b", files("b"));
+        let temp_dir = fixture(&[
+            (&a, &[("$data/common.dll", ""), ("$plugin/a.auo2", "")]),
+            (&b, &[("$data/common.dll", ""), ("$plugin/b.auo2", "")]),
+        ])
+        .await;
+
+        let fs = VirtualFs::new(temp_dir.path());
+        let usages = InstallPlan::default()
+            .installed_usages(&fs, &a)
+            .await
+            .unwrap();
+        let removed = InstallPlan::exclude_shared_usages(&fs, &usages)
+            .await
+            .unwrap();
+        assert_eq!(
+            removed
+                .iter()
+                .map(|(_, usage)| usage.path.to_string())
+                .collect::<Vec<_>>(),
+            ["$plugin/a.auo2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let old = manifest(
+            "test-a",
+            serde_json::json!({ "resources": [
+                { "source": "https://example.com/a.anm2", "destination": "$script/a.anm2" },
+                { "source": "https://example.com/old.anm2", "destination": "$script/old.anm2" },
+            ] }),
+        );
+        let sha256 = format!("{:x}", sha2::Sha256::digest(b"new"));
+        let new = manifest(
+            "test-a",
+            serde_json::json!({ "resources": [
+                { "source": "https://example.com/a.anm2", "destination": "$script/a.anm2", "sha256": sha256 },
+                { "source": "https://example.com/b.anm2", "destination": "$script/b.anm2" },
+            ] }),
+        );
+        let temp_dir = fixture(&[(
+            &old,
+            &[("$script/a.anm2", "aaaaa"), ("$script/old.anm2", "old")],
+        )])
+        .await;
+        let root = temp_dir.path();
+        fs_err::tokio::write(root.join("Script/b.anm2"), "bb")
+            .await
+            .unwrap();
+        let cache = DownloadCache::new(root.join("cache"));
+        let cached = root.join("cached.anm2");
+        fs_err::tokio::write(&cached, "new").await.unwrap();
+        cache
+            .insert(&CacheKey::Sha256(sha256), &cached, None, u64::MAX)
+            .await
+            .unwrap();
+
+        let plan = InstallPlan {
+            to_update: vec![(old, new)],
+            untracked: [(
+                std::path::PathBuf::from("Script/b.anm2"),
+                UntrackedFileResolution::Overwrite,
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let operations = plan
+            .dry_run(root, &BundleListings::new(), &cache)
+            .await
+            .unwrap();
+        let id = "test-a".parse::<crate::models::ManifestId>().unwrap();
+        let path = |path: &str| std::path::PathBuf::from(path);
+        assert_eq!(
+            operations,
+            [
+                FileOperation::Overwrite {
+                    manifest_id: id.clone(),
+                    path: path("Script/a.anm2"),
+                    size: Some(3),
+                    previous_size: Some(5),
+                },
+                FileOperation::Backup {
+                    manifest_id: id.clone(),
+                    path: path("Script/b.anm2"),
+                    size: Some(2),
+                },
+                FileOperation::Overwrite {
+                    manifest_id: id.clone(),
+                    path: path("Script/b.anm2"),
+                    size: None,
+                    previous_size: Some(2),
+                },
+                FileOperation::Delete {
+                    manifest_id: id.clone(),
+                    path: path("Script/old.anm2"),
+                    size: Some(3),
+                },
+            ]
+        );
+        // ディスクには何も書き込まれていない
+        assert_eq!(
+            fs_err::tokio::read(root.join("Script/a.anm2"))
+                .await
+                .unwrap(),
+            b"aaaaa"
+        );
+        assert!(root.join("Script/old.anm2").exists());
+    }
 }
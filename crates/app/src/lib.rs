@@ -1,12 +1,28 @@
+mod adopt;
+mod archive;
+mod cache;
 mod commands;
+mod config_backup;
+mod download;
+mod drift;
+mod environment;
 mod ext;
 mod fetch;
+mod generation;
 mod installer;
+mod lockfile;
 mod models;
+mod orphan;
 mod path_match;
+mod receipt;
 mod store;
+mod transaction;
 mod utils;
+mod verify;
+mod vfs;
+mod zip_range;
 use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
+use tauri::Manager;
 use utils::anyhow_to_string;
 
 #[tauri::command]
@@ -183,8 +199,234 @@ async fn plan_installation(
     handle: tauri::AppHandle,
     profile_id: uuid::Uuid,
     desired_manifests: Vec<crate::models::Manifest>,
+    resolutions: Option<
+        std::collections::HashMap<std::path::PathBuf, installer::UntrackedFileResolution>,
+    >,
 ) -> Result<installer::InstallPlan, String> {
-    commands::plan_installation(&handle, profile_id, desired_manifests)
+    commands::plan_installation(
+        &handle,
+        profile_id,
+        desired_manifests,
+        resolutions.unwrap_or_default(),
+    )
+    .await
+    .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn scan_and_adopt(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> Result<adopt::AdoptResult, String> {
+    commands::scan_and_adopt(&handle, profile_id)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn list_orphans(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> Result<std::collections::BTreeMap<String, Vec<orphan::OrphanFile>>, String> {
+    commands::list_orphans(&handle, profile_id)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn quarantine_orphans(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    paths: Vec<std::path::PathBuf>,
+) -> Result<std::path::PathBuf, String> {
+    commands::quarantine_orphans(&handle, profile_id, paths)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn verify_profile(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    repair: bool,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> Result<std::collections::BTreeMap<crate::models::ManifestId, verify::VerifyResult>, String> {
+    commands::verify_profile(&handle, profile_id, repair, ch)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn list_config_backups(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    manifest_id: Option<crate::models::ManifestId>,
+) -> Result<Vec<config_backup::ConfigBackup>, String> {
+    commands::list_config_backups(&handle, profile_id, manifest_id)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn preview_config_backup(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    manifest_id: crate::models::ManifestId,
+    file_name: String,
+) -> Result<Vec<config_backup::ConfigBackupEntry>, String> {
+    commands::preview_config_backup(&handle, profile_id, manifest_id, file_name)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn restore_config_backup(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    manifest_id: crate::models::ManifestId,
+    file_name: String,
+    target_profile_id: Option<uuid::Uuid>,
+) -> Result<Vec<std::path::PathBuf>, String> {
+    commands::restore_config_backup(
+        &handle,
+        profile_id,
+        manifest_id,
+        file_name,
+        target_profile_id,
+    )
+    .await
+    .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn export_profile(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    dest: std::path::PathBuf,
+) -> Result<(), String> {
+    commands::export_profile(&handle, profile_id, dest)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn import_profile(
+    handle: tauri::AppHandle,
+    archive: std::path::PathBuf,
+    name: Option<String>,
+    path: std::path::PathBuf,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> Result<uuid::Uuid, String> {
+    commands::import_profile(&handle, archive, name, path, ch)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn generate_lockfile(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> Result<std::path::PathBuf, String> {
+    commands::generate_lockfile(&handle, profile_id)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn sync_from_lockfile(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    lockfile: Option<std::path::PathBuf>,
+    resolutions: Option<
+        std::collections::HashMap<std::path::PathBuf, installer::UntrackedFileResolution>,
+    >,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> Result<installer::InstallPlan, String> {
+    commands::sync_from_lockfile(
+        &handle,
+        profile_id,
+        lockfile,
+        resolutions.unwrap_or_default(),
+        ch,
+    )
+    .await
+    .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn list_generations(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+) -> Result<Vec<generation::GenerationSummary>, String> {
+    commands::list_generations(&handle, profile_id)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn rollback_to_generation(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    generation: u64,
+    resolutions: Option<
+        std::collections::HashMap<std::path::PathBuf, installer::UntrackedFileResolution>,
+    >,
+    ch: tauri::ipc::Channel<(crate::models::ManifestId, crate::installer::InstallProgress)>,
+) -> Result<installer::InstallPlan, String> {
+    commands::rollback_to_generation(
+        &handle,
+        profile_id,
+        generation,
+        resolutions.unwrap_or_default(),
+        ch,
+    )
+    .await
+    .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn detect_drift(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    lockfile: Option<std::path::PathBuf>,
+    composition: Option<Vec<crate::models::Manifest>>,
+    check_upstream: Option<bool>,
+) -> Result<drift::DriftReport, String> {
+    commands::detect_drift(
+        &handle,
+        profile_id,
+        lockfile,
+        composition,
+        check_upstream.unwrap_or(true),
+    )
+    .await
+    .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn get_settings(handle: tauri::AppHandle) -> Result<store::Settings, String> {
+    commands::get_settings(&handle)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn set_settings(handle: tauri::AppHandle, settings: store::Settings) -> Result<(), String> {
+    commands::set_settings(&handle, settings)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn get_cache_info(handle: tauri::AppHandle) -> Result<cache::CacheInfo, String> {
+    commands::get_cache_info(&handle)
+        .await
+        .map_err(anyhow_to_string)
+}
+
+#[tauri::command]
+async fn clear_cache(handle: tauri::AppHandle) -> Result<(), String> {
+    commands::clear_cache(&handle)
         .await
         .map_err(anyhow_to_string)
 }
@@ -201,6 +443,17 @@ async fn perform_installation(
         .map_err(anyhow_to_string)
 }
 
+#[tauri::command]
+async fn dry_run_installation(
+    handle: tauri::AppHandle,
+    profile_id: uuid::Uuid,
+    plan: installer::InstallPlan,
+) -> Result<Vec<vfs::FileOperation>, String> {
+    commands::dry_run_installation(&handle, profile_id, plan)
+        .await
+        .map_err(anyhow_to_string)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -215,6 +468,16 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            app.manage(cache::DownloadCache::new(utils::cache_dir(app.handle())?));
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = commands::recover_transactions(&handle).await {
+                    log::error!("Failed to recover transactions: {e:?}");
+                }
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             initialize_profile,
             list_profiles,
@@ -236,6 +499,25 @@ pub fn run() {
             remove_manifest,
             plan_installation,
             perform_installation,
+            dry_run_installation,
+            scan_and_adopt,
+            list_orphans,
+            quarantine_orphans,
+            verify_profile,
+            list_config_backups,
+            preview_config_backup,
+            restore_config_backup,
+            export_profile,
+            import_profile,
+            generate_lockfile,
+            sync_from_lockfile,
+            list_generations,
+            rollback_to_generation,
+            detect_drift,
+            get_settings,
+            set_settings,
+            get_cache_info,
+            clear_cache,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,338 @@
+use crate::installer::DATA_DIR;
+use crate::receipt::Receipt;
+
+pub static LOCKFILE: &str = "lock.yml";
+
+/// ロックファイルの形式のバージョン。
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// プロファイルにインストールされているものを固定するロックファイル。`au2ec/lock.yml`に保存される。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Lockfile {
+    pub lockfile_version: u32,
+    pub manifests: std::collections::BTreeMap<crate::models::ManifestId, LockedManifest>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LockedManifest {
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_number: Option<u64>,
+    /// マニフェストを取得したURL。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_url: Option<url::Url>,
+    pub resources: Vec<LockedResource>,
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub bundles: std::collections::BTreeMap<String, LockedBundle>,
+    /// 固定したマニフェスト。同期するときは、`manifest_url`から取得し直さずにこれを使う。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<crate::models::Manifest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockedResource {
+    pub source: url::Url,
+    /// ファイルのSHA256ハッシュ値。ディレクトリに展開されるリソースの場合は`None`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockedBundle {
+    pub source: url::Url,
+    /// バンドルのSHA256ハッシュ値。マニフェストに書かれていない場合は`None`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl LockedManifest {
+    /// マニフェストにSHA256ハッシュ値が書かれていないリソースは、`receipt`に記録された値を使います。
+    pub fn new(manifest: &crate::models::Manifest, receipt: Option<&Receipt>) -> Self {
+        let resources = manifest
+            .resources
+            .iter()
+            .map(|resource| LockedResource {
+                source: resource.source.0.clone(),
+                sha256: resource.sha256.clone().or_else(|| {
+                    receipt
+                        .iter()
+                        .flat_map(|receipt| receipt.files.iter())
+                        .find(|file| file.path == resource.destination)
+                        .map(|file| file.sha256.clone())
+                }),
+            })
+            .collect();
+        let bundles = manifest
+            .bundles
+            .iter()
+            .flatten()
+            .map(|(name, bundle)| {
+                (
+                    name.to_string(),
+                    LockedBundle {
+                        source: bundle.url().0.clone(),
+                        sha256: bundle.sha256().map(str::to_owned),
+                    },
+                )
+            })
+            .collect();
+        Self {
+            version: manifest.version.to_string(),
+            version_number: manifest.version_number.as_ref().map(|v| v.0),
+            manifest_url: manifest.manifest_url.as_ref().map(|url| url.0.clone()),
+            resources,
+            bundles,
+            manifest: Some(manifest.clone()),
+        }
+    }
+}
+
+impl Lockfile {
+    /// データディレクトリからの、ロックファイルの相対パス。
+    pub fn relative_path() -> std::path::PathBuf {
+        std::path::Path::new(DATA_DIR).join(LOCKFILE)
+    }
+
+    /// `root`にインストールされている`installed`からロックファイルを作る。
+    pub async fn generate(
+        root: &std::path::Path,
+        installed: &[crate::models::Manifest],
+    ) -> anyhow::Result<Self> {
+        let mut manifests = std::collections::BTreeMap::new();
+        for manifest in installed {
+            let receipt = Receipt::load(root, &manifest.id).await?;
+            manifests.insert(
+                manifest.id.clone(),
+                LockedManifest::new(manifest, receipt.as_ref()),
+            );
+        }
+        Ok(Self {
+            lockfile_version: LOCKFILE_VERSION,
+            manifests,
+        })
+    }
+
+    /// 構成（インストールしたいマニフェストの一覧）からロックファイルを作る。
+    pub fn from_manifests(manifests: &[crate::models::Manifest]) -> Self {
+        Self {
+            lockfile_version: LOCKFILE_VERSION,
+            manifests: manifests
+                .iter()
+                .map(|manifest| (manifest.id.clone(), LockedManifest::new(manifest, None)))
+                .collect(),
+        }
+    }
+
+    pub async fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let lockfile: Self = serde_yml::from_slice(&fs_err::tokio::read(path).await?)?;
+        if lockfile.lockfile_version > LOCKFILE_VERSION {
+            anyhow::bail!(
+                "#unsupported_lockfile_version[{}]",
+                serde_json::to_string(&lockfile.lockfile_version).unwrap()
+            );
+        }
+        Ok(lockfile)
+    }
+
+    /// `root`の`au2ec/lock.yml`に書き出し、そのパスを返す。
+    pub async fn save(&self, root: &std::path::Path) -> anyhow::Result<std::path::PathBuf> {
+        let path = root.join(Self::relative_path());
+        fs_err::tokio::create_dir_all(path.parent().unwrap()).await?;
+        fs_err::tokio::write(&path, serde_yml::to_string(self)?).await?;
+        Ok(path)
+    }
+
+    /// `manifest`がロックファイルに記録されたものと同じか確かめ、
+    /// マニフェストに無いSHA256ハッシュ値をロックファイルのもので補う。バンドルも同様に確かめる。
+    pub fn pin(
+        &self,
+        mut manifest: crate::models::Manifest,
+    ) -> anyhow::Result<crate::models::Manifest> {
+        let Some(locked) = self.manifests.get(&manifest.id) else {
+            anyhow::bail!(
+                "#not_in_lockfile[{}]",
+                serde_json::to_string(&manifest.id).unwrap()
+            );
+        };
+        let version = manifest.version.to_string();
+        if version != locked.version {
+            anyhow::bail!(
+                "#lockfile_version_mismatch[{},{},{}]",
+                serde_json::to_string(&manifest.id).unwrap(),
+                serde_json::to_string(&locked.version).unwrap(),
+                serde_json::to_string(&version).unwrap()
+            );
+        }
+        let version_number = manifest.version_number.as_ref().map(|v| v.0);
+        if let Some(locked_number) = locked.version_number {
+            if version_number != Some(locked_number) {
+                anyhow::bail!(
+                    "#lockfile_version_number_mismatch[{},{},{}]",
+                    serde_json::to_string(&manifest.id).unwrap(),
+                    serde_json::to_string(&locked_number.to_string()).unwrap(),
+                    serde_json::to_string(
+                        &version_number.map_or_else(|| "-".to_owned(), |n| n.to_string())
+                    )
+                    .unwrap()
+                );
+            }
+        }
+        if manifest.resources.len() != locked.resources.len() {
+            anyhow::bail!(
+                "#lockfile_resources_mismatch[{}]",
+                serde_json::to_string(&manifest.id).unwrap()
+            );
+        }
+        for (resource, locked) in manifest.resources.iter_mut().zip(&locked.resources) {
+            let matches = resource.source.0 == locked.source
+                && match (&resource.sha256, &locked.sha256) {
+                    (Some(actual), Some(expected)) => actual.eq_ignore_ascii_case(expected),
+                    _ => true,
+                };
+            if !matches {
+                anyhow::bail!(
+                    "#lockfile_resources_mismatch[{}]",
+                    serde_json::to_string(&manifest.id).unwrap()
+                );
+            }
+            if resource.sha256.is_none() {
+                resource.sha256 = locked.sha256.clone();
+            }
+        }
+        // NOTE: バンドルが記録されていない古いロックファイルでは確かめない
+        if !locked.bundles.is_empty() {
+            let bundles = manifest.bundles.iter_mut().flatten().collect::<Vec<_>>();
+            if bundles.len() != locked.bundles.len() {
+                anyhow::bail!(
+                    "#lockfile_resources_mismatch[{}]",
+                    serde_json::to_string(&manifest.id).unwrap()
+                );
+            }
+            for (name, bundle) in bundles {
+                let matches = locked.bundles.get(name.as_str()).is_some_and(|locked| {
+                    bundle.url().0 == locked.source
+                        && match (bundle.sha256(), &locked.sha256) {
+                            (Some(actual), Some(expected)) => actual.eq_ignore_ascii_case(expected),
+                            _ => true,
+                        }
+                });
+                if !matches {
+                    anyhow::bail!(
+                        "#lockfile_resources_mismatch[{}]",
+                        serde_json::to_string(&manifest.id).unwrap()
+                    );
+                }
+                if let (None, Some(sha256)) =
+                    (bundle.sha256(), &locked.bundles[name.as_str()].sha256)
+                {
+                    *bundle =
+                        crate::models::Bundle::BundleDefinition(crate::models::BundleDefinition {
+                            encoding: bundle.encoding().map(str::to_owned),
+                            sha256: Some(sha256.clone()),
+                            url: bundle.url().clone(),
+                        });
+                }
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(sha256: Option<&str>) -> crate::models::Manifest {
+        serde_json::from_value(serde_json::json!({
+            "manifest_version": 1,
+            "id": "test-lock",
+            "name": "test-lock",
+            "summary": "",
+            "version": "1.0.0",
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "resources": [{
+                "source": "https://example.com/a.anm2",
+                "destination": "$script/a.anm2",
+                "sha256": sha256,
+            }],
+        }))
+        .unwrap()
+    }
+
+    #[rstest::rstest]
+    #[case::fills_sha256(None, Some("abc"), true)]
+    #[case::same_sha256(Some("ABC"), Some("abc"), true)]
+    #[case::different_sha256(Some("abc"), Some("def"), false)]
+    #[case::unlocked_sha256(Some("abc"), None, true)]
+    fn test_pin(
+        #[case] manifest_sha256: Option<&str>,
+        #[case] locked_sha256: Option<&str>,
+        #[case] ok: bool,
+    ) {
+        let lockfile = Lockfile {
+            lockfile_version: LOCKFILE_VERSION,
+            manifests: [(
+                "test-lock".parse().unwrap(),
+                LockedManifest {
+                    version: "1.0.0".to_owned(),
+                    version_number: None,
+                    manifest_url: None,
+                    resources: vec![LockedResource {
+                        source: "https://example.com/a.anm2".parse().unwrap(),
+                        sha256: locked_sha256.map(str::to_owned),
+                    }],
+                    bundles: Default::default(),
+                    manifest: None,
+                },
+            )]
+            .into(),
+        };
+        let pinned = lockfile.pin(manifest(manifest_sha256));
+        assert_eq!(pinned.is_ok(), ok);
+        if let Ok(pinned) = pinned {
+            assert!(pinned.resources[0].sha256.is_some() || locked_sha256.is_none());
+        }
+    }
+
+    #[rstest::rstest]
+    #[case::same(Some(1), "https://example.com/main.zip", None, true)]
+    #[case::different_version_number(Some(2), "https://example.com/main.zip", None, false)]
+    #[case::missing_version_number(None, "https://example.com/main.zip", None, false)]
+    #[case::different_bundle_source(Some(1), "https://example.com/other.zip", None, false)]
+    #[case::different_bundle_sha256(Some(1), "https://example.com/main.zip", Some("abc"), false)]
+    fn test_pin_version_number_and_bundles(
+        #[case] version_number: Option<u64>,
+        #[case] bundle_source: &str,
+        #[case] bundle_sha256: Option<&str>,
+        #[case] ok: bool,
+    ) {
+        let locked = serde_json::from_value::<crate::models::Manifest>(serde_json::json!({
+            "manifest_version": 1,
+            "id": "test-lock",
+            "name": "test-lock",
+            "summary": "",
+            "version": "1.0.0",
+            "version_number": 1,
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "bundles": { "main": { "url": "https://example.com/main.zip", "sha256": "def" } },
+            "resources": [],
+        }))
+        .unwrap();
+        let lockfile = Lockfile::from_manifests(std::slice::from_ref(&locked));
+        let mut manifest = serde_json::to_value(&locked).unwrap();
+        manifest["version_number"] = serde_json::json!(version_number);
+        manifest["bundles"]["main"] = match bundle_sha256 {
+            Some(sha256) => serde_json::json!({ "url": bundle_source, "sha256": sha256 }),
+            None => serde_json::json!(bundle_source),
+        };
+        let pinned = lockfile.pin(serde_json::from_value(manifest).unwrap());
+        assert_eq!(pinned.is_ok(), ok);
+        if let Ok(pinned) = pinned {
+            // マニフェストに無いバンドルのハッシュ値はロックファイルのもので補われる
+            let bundles = pinned.bundles.unwrap();
+            assert_eq!(bundles.values().next().unwrap().sha256(), Some("def"));
+        }
+    }
+}
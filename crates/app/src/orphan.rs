@@ -0,0 +1,125 @@
+use crate::installer::{list_root_type_files, root_type_dir, DATA_DIR};
+use crate::receipt::owned_paths;
+
+pub static QUARANTINE_DIR: &str = "quarantine";
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OrphanFile {
+    pub path: std::path::PathBuf,
+    pub size: u64,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// どのマニフェストにも属していないファイルを、`RootType`（`$plugin`など）ごとにまとめて返す。
+pub async fn list_orphans(
+    root: &std::path::Path,
+    installed: &[crate::models::Manifest],
+) -> anyhow::Result<std::collections::BTreeMap<String, Vec<OrphanFile>>> {
+    use strum::IntoEnumIterator;
+
+    let owned_paths = owned_paths(root, installed).await?;
+    let mut orphans = std::collections::BTreeMap::<String, Vec<OrphanFile>>::new();
+    for path in list_root_type_files(root).await? {
+        if owned_paths.iter().any(|owned| path.starts_with(owned)) {
+            continue;
+        }
+        let Some(root_type) = crate::models::RootType::iter()
+            .find(|root_type| root_type_dir(*root_type).is_some_and(|dir| path.starts_with(dir)))
+        else {
+            continue;
+        };
+        let metadata = fs_err::tokio::metadata(root.join(&path)).await?;
+        orphans
+            .entry(root_type.to_string())
+            .or_default()
+            .push(OrphanFile {
+                path,
+                size: metadata.len(),
+                modified: metadata.modified().ok().map(Into::into),
+            });
+    }
+    Ok(orphans)
+}
+
+/// `paths`（データディレクトリからの相対パス）を`au2ec/quarantine/{日時}`に移動する。
+/// どのマニフェストにも属していないファイル以外は移動しません。
+///
+/// 移動先のディレクトリを返します。
+pub async fn quarantine_orphans(
+    root: &std::path::Path,
+    installed: &[crate::models::Manifest],
+    paths: &[std::path::PathBuf],
+) -> anyhow::Result<std::path::PathBuf> {
+    let orphans = list_orphans(root, installed).await?;
+    let orphan_paths: std::collections::HashSet<&std::path::Path> = orphans
+        .values()
+        .flatten()
+        .map(|orphan| orphan.path.as_path())
+        .collect();
+    if let Some(path) = paths
+        .iter()
+        .find(|path| !orphan_paths.contains(path.as_path()))
+    {
+        anyhow::bail!("#not_orphan[{}]", serde_json::to_string(path)?);
+    }
+
+    let quarantine_dir = root
+        .join(DATA_DIR)
+        .join(QUARANTINE_DIR)
+        .join(chrono::Local::now().format("%Y%m%d%H%M%S").to_string());
+    for path in paths {
+        let dest = quarantine_dir.join(path);
+        if let Some(parent) = dest.parent() {
+            fs_err::tokio::create_dir_all(parent).await?;
+        }
+        fs_err::tokio::rename(root.join(path), &dest).await?;
+        log::info!("Quarantined {path:?}");
+    }
+    Ok(quarantine_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_configurations_are_not_orphans() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs_err::tokio::create_dir_all(root.join("Plugin/test/cache"))
+            .await
+            .unwrap();
+        fs_err::tokio::write(root.join("Plugin/test/config.ini"), "a=1")
+            .await
+            .unwrap();
+        fs_err::tokio::write(root.join("Plugin/test/cache/data.bin"), "cache")
+            .await
+            .unwrap();
+        fs_err::tokio::write(root.join("Plugin/orphan.auf2"), "orphan")
+            .await
+            .unwrap();
+        let manifest: crate::models::Manifest = serde_json::from_value(serde_json::json!({
+            "manifest_version": 1,
+            "id": "test-orphan",
+            "name": "test-orphan",
+            "summary": "",
+            "version": "1.0.0",
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "resources": [],
+            "configurations": [{ "path": "$plugin/test/config.ini" }],
+            "disposables": [{ "path": "$plugin/test/cache/" }],
+        }))
+        .unwrap();
+
+        let orphans = list_orphans(root, &[manifest]).await.unwrap();
+        assert_eq!(
+            orphans
+                .values()
+                .flatten()
+                .map(|orphan| orphan.path.clone())
+                .collect::<Vec<_>>(),
+            [std::path::PathBuf::from("Plugin/orphan.auf2")]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::installer::{DataDirPathExt, DATA_DIR};
+
+pub static RECEIPTS_DIR: &str = "receipts";
+
+/// インストールしたマニフェストが実際に書き込んだファイルの一覧。
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Receipt {
+    pub id: crate::models::ManifestId,
+    pub version: crate::models::Version,
+    pub files: Vec<ReceiptFile>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+pub struct ReceiptFile {
+    pub path: crate::models::DataDirRelativePath,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Receipt {
+    /// データディレクトリからの、レシートファイルの相対パス。
+    pub fn relative_path(id: &crate::models::ManifestId) -> std::path::PathBuf {
+        std::path::Path::new(DATA_DIR)
+            .join(RECEIPTS_DIR)
+            .join(format!("{id}.json"))
+    }
+
+    pub async fn load(
+        root: &std::path::Path,
+        id: &crate::models::ManifestId,
+    ) -> anyhow::Result<Option<Self>> {
+        let path = root.join(Self::relative_path(id));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs_err::tokio::read(&path).await?;
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+
+    /// `root`に記録されている全てのレシート。
+    pub async fn load_all(root: &std::path::Path) -> anyhow::Result<Vec<Self>> {
+        let dir = root.join(DATA_DIR).join(RECEIPTS_DIR);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut receipts = Vec::new();
+        let mut entries = fs_err::tokio::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                receipts.push(serde_json::from_slice(&fs_err::tokio::read(&path).await?)?);
+            }
+        }
+        Ok(receipts)
+    }
+}
+
+/// `manifests`が所有するファイルの、データディレクトリからの相対パス。
+/// レシートがない場合はマニフェストの`resources`から推測する。
+///
+/// 設定ファイル（`configurations`）と使い捨てのファイル（`disposables`）も所有しているものとして扱います。
+pub async fn owned_paths(
+    root: &std::path::Path,
+    manifests: &[crate::models::Manifest],
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let mut paths = Vec::new();
+    for manifest in manifests {
+        match Receipt::load(root, &manifest.id).await? {
+            Some(receipt) => paths.extend(receipt.files.iter().map(|f| f.path.as_relative_path())),
+            None => paths.extend(
+                manifest
+                    .resources
+                    .iter()
+                    .map(|r| r.destination.as_relative_path()),
+            ),
+        }
+        paths.extend(
+            manifest
+                .configurations
+                .iter()
+                .map(|c| c.path.as_relative_path()),
+        );
+        paths.extend(
+            manifest
+                .disposables
+                .iter()
+                .map(|d| d.path.as_relative_path()),
+        );
+        paths.push(Receipt::relative_path(&manifest.id));
+    }
+    Ok(paths)
+}
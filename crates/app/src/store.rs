@@ -152,6 +152,35 @@ pub struct IndexStore {
     pub registries: std::collections::BTreeMap<uuid::Uuid, url::Url>,
     #[serde(default)]
     pub manifests: std::collections::BTreeMap<uuid::Uuid, url::Url>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// 同時にダウンロードするファイルの数。
+    pub download_concurrency: usize,
+    /// ダウンロードキャッシュの最大サイズ（バイト）。
+    pub cache_max_size: u64,
+    /// マニフェストごとに残す設定のバックアップの数。`None`の場合は数では削除しない。
+    pub config_backup_max_count: Option<usize>,
+    /// 設定のバックアップを残す日数。`None`の場合は日数では削除しない。
+    pub config_backup_max_age_days: Option<u64>,
+    /// 残す世代の数。`None`の場合は削除しない。
+    pub generation_max_count: Option<usize>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            download_concurrency: 4,
+            cache_max_size: 2 * 1024 * 1024 * 1024,
+            config_backup_max_count: Some(10),
+            config_backup_max_age_days: None,
+            generation_max_count: Some(20),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
@@ -176,17 +205,40 @@ impl Store for IndexStore {
 pub struct ProfileStore {
     pub name: String,
 
-    pub contents: std::collections::BTreeMap<crate::models::ManifestId, crate::models::Manifest>,
+    pub contents: std::collections::BTreeMap<crate::models::ManifestId, InstalledManifest>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct InstalledManifest {
+    pub manifest: crate::models::Manifest,
+    pub installed_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub registry: Option<url::Url>,
 }
 
 impl Store for ProfileStore {
-    const CURRENT_VERSION: u32 = 1;
+    const CURRENT_VERSION: u32 = 2;
     fn migrate(
-        _from: u32,
+        from: u32,
         _to: u32,
-        value: serde_json::Value,
+        mut value: serde_json::Value,
     ) -> anyhow::Result<serde_json::Value> {
-        // NOTE: 今回はマイグレーションがないのでそのまま返す
+        if from < 2 {
+            // v1では`contents`にマニフェストをそのまま保存していた
+            if let Some(contents) = value
+                .get_mut("contents")
+                .and_then(serde_json::Value::as_object_mut)
+            {
+                let installed_at = serde_json::to_value(chrono::Utc::now())?;
+                for manifest in contents.values_mut() {
+                    *manifest = serde_json::json!({
+                        "manifest": manifest.take(),
+                        "installed_at": installed_at,
+                        "registry": null,
+                    });
+                }
+            }
+        }
         Ok(value)
     }
 }
@@ -0,0 +1,406 @@
+use crate::installer::{DataDirPathExt, DATA_DIR, TEMP_DIR};
+use crate::vfs::{FileSystem, StagedFile, Workspace};
+
+pub static TRANSACTIONS_DIR: &str = "transactions";
+static JOURNAL_FILE: &str = "journal.json";
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JournalEntry {
+    Created {
+        path: std::path::PathBuf,
+    },
+    /// ファイルを配置するために作ったディレクトリ。
+    CreatedDir {
+        path: std::path::PathBuf,
+    },
+    Replaced {
+        path: std::path::PathBuf,
+        backup: std::path::PathBuf,
+    },
+    Removed {
+        path: std::path::PathBuf,
+        backup: std::path::PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct Journal {
+    manifest_id: crate::models::ManifestId,
+    committed: bool,
+    entries: Vec<JournalEntry>,
+}
+
+/// データディレクトリへの変更をまとめて行い、失敗したときに元に戻せるようにするもの。
+///
+/// 変更を行う前に必ずジャーナルを書き込むので、途中でクラッシュした場合も
+/// [`Transaction::recover`]で変更前の状態に戻すことができます。
+#[derive(Debug)]
+pub struct Transaction {
+    root: std::path::PathBuf,
+    dir: std::path::PathBuf,
+    journal: Journal,
+}
+
+impl Transaction {
+    pub async fn begin(
+        root: &std::path::Path,
+        manifest_id: &crate::models::ManifestId,
+    ) -> anyhow::Result<Self> {
+        let dir = transactions_dir(root).join(uuid::Uuid::now_v7().to_string());
+        fs_err::tokio::create_dir_all(dir.join("staging")).await?;
+        fs_err::tokio::create_dir_all(dir.join("backup")).await?;
+        let transaction = Self {
+            root: root.to_path_buf(),
+            dir,
+            journal: Journal {
+                manifest_id: manifest_id.clone(),
+                committed: false,
+                entries: Vec::new(),
+            },
+        };
+        transaction.save_journal().await?;
+        Ok(transaction)
+    }
+
+    /// インストールするファイルを一時的に置くディレクトリ。
+    pub fn staging_dir(&self) -> std::path::PathBuf {
+        self.dir.join("staging")
+    }
+
+    /// `staged`を`relative`（データディレクトリからの相対パス）に配置する。
+    /// 既にファイルが存在する場合はバックアップされます。
+    pub async fn place(
+        &mut self,
+        staged: &std::path::Path,
+        relative: &std::path::Path,
+    ) -> anyhow::Result<()> {
+        let dest = self.root.join(relative);
+        if fs_err::tokio::symlink_metadata(&dest).await.is_ok() {
+            let backup = self.next_backup_path();
+            self.push_entry(JournalEntry::Replaced {
+                path: relative.to_path_buf(),
+                backup: backup.clone(),
+            })
+            .await?;
+            move_path(&dest, &self.dir.join(&backup)).await?;
+        } else {
+            let mut created_dirs = Vec::new();
+            for dir in relative.ancestors().skip(1) {
+                if dir.as_os_str().is_empty()
+                    || fs_err::tokio::symlink_metadata(self.root.join(dir))
+                        .await
+                        .is_ok()
+                {
+                    break;
+                }
+                created_dirs.push(dir.to_path_buf());
+            }
+            // NOTE: ロールバック時は逆順に処理されるので、浅い方から記録する
+            for dir in created_dirs.into_iter().rev() {
+                self.push_entry(JournalEntry::CreatedDir { path: dir })
+                    .await?;
+            }
+            self.push_entry(JournalEntry::Created {
+                path: relative.to_path_buf(),
+            })
+            .await?;
+        }
+        if let Some(parent) = dest.parent() {
+            fs_err::tokio::create_dir_all(parent).await?;
+        }
+        move_path(staged, &dest).await?;
+        Ok(())
+    }
+
+    /// `relative`（データディレクトリからの相対パス）を削除する。
+    /// 削除したファイルはコミットされるまでバックアップとして保持されます。
+    pub async fn remove(&mut self, relative: &std::path::Path) -> anyhow::Result<()> {
+        let path = self.root.join(relative);
+        if fs_err::tokio::symlink_metadata(&path).await.is_err() {
+            return Ok(());
+        }
+        let backup = self.next_backup_path();
+        self.push_entry(JournalEntry::Removed {
+            path: relative.to_path_buf(),
+            backup: backup.clone(),
+        })
+        .await?;
+        move_path(&path, &self.dir.join(&backup)).await?;
+        Ok(())
+    }
+
+    pub async fn commit(mut self) -> anyhow::Result<()> {
+        self.journal.committed = true;
+        self.save_journal().await?;
+        fs_err::tokio::remove_dir_all(&self.dir).await?;
+        log::info!("Transaction committed: {}", self.journal.manifest_id);
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> anyhow::Result<()> {
+        rollback_journal(&self.root, &self.dir, &self.journal).await?;
+        fs_err::tokio::remove_dir_all(&self.dir).await?;
+        log::warn!("Transaction rolled back: {}", self.journal.manifest_id);
+        Ok(())
+    }
+
+    /// 前回の実行で完了しなかったトランザクションを元に戻す。
+    pub async fn recover(root: &std::path::Path) -> anyhow::Result<()> {
+        let transactions_dir = transactions_dir(root);
+        if !transactions_dir.exists() {
+            return Ok(());
+        }
+        let mut entries = fs_err::tokio::read_dir(&transactions_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let dir = entry.path();
+            let journal_path = dir.join(JOURNAL_FILE);
+            if journal_path.exists() {
+                let journal: Journal =
+                    serde_json::from_slice(&fs_err::tokio::read(&journal_path).await?)?;
+                if !journal.committed {
+                    log::warn!(
+                        "Recovering interrupted transaction: {}, {:?}",
+                        journal.manifest_id,
+                        dir
+                    );
+                    rollback_journal(root, &dir, &journal).await?;
+                }
+            }
+            fs_err::tokio::remove_dir_all(&dir).await?;
+        }
+        Ok(())
+    }
+
+    fn next_backup_path(&self) -> std::path::PathBuf {
+        std::path::Path::new("backup").join(self.journal.entries.len().to_string())
+    }
+
+    async fn push_entry(&mut self, entry: JournalEntry) -> anyhow::Result<()> {
+        self.journal.entries.push(entry);
+        self.save_journal().await
+    }
+
+    async fn save_journal(&self) -> anyhow::Result<()> {
+        let journal_path = self.dir.join(JOURNAL_FILE);
+        let tmp_path = self.dir.join(format!("{JOURNAL_FILE}.tmp"));
+        fs_err::tokio::write(&tmp_path, serde_json::to_vec_pretty(&self.journal)?).await?;
+        fs_err::tokio::rename(&tmp_path, &journal_path).await?;
+        Ok(())
+    }
+}
+
+/// `f`をトランザクション内で実行し、成功したらコミット、失敗したらロールバックする。
+pub async fn transactional<T>(
+    root: &std::path::Path,
+    manifest_id: &crate::models::ManifestId,
+    f: impl AsyncFnOnce(&mut Transaction) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let mut transaction = Transaction::begin(root, manifest_id).await?;
+    match f(&mut transaction).await {
+        Ok(value) => {
+            transaction.commit().await?;
+            Ok(value)
+        }
+        Err(e) => {
+            if let Err(rollback_error) = transaction.rollback().await {
+                log::error!("Failed to rollback transaction: {rollback_error:?}");
+            }
+            Err(e)
+        }
+    }
+}
+
+/// マニフェストごとにトランザクションを作り、データディレクトリを実際に変更するもの。
+#[derive(Debug)]
+pub struct Transactions {
+    root: std::path::PathBuf,
+}
+
+impl Transactions {
+    pub fn new(root: &std::path::Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+}
+
+impl Workspace for Transactions {
+    type Fs = Transaction;
+
+    async fn run(
+        &mut self,
+        manifest_id: &crate::models::ManifestId,
+        f: impl AsyncFnOnce(&mut Transaction) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        transactional(&self.root, manifest_id, f).await
+    }
+}
+
+impl FileSystem for Transaction {
+    fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    fn staging_dir(&self) -> Option<std::path::PathBuf> {
+        Some(Transaction::staging_dir(self))
+    }
+
+    async fn place_file(&mut self, file: &StagedFile) -> anyhow::Result<()> {
+        let staged = file
+            .staged
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("File is not staged: {}", file.path))?;
+        self.place(staged, &file.path.as_relative_path()).await
+    }
+
+    async fn remove(&mut self, relative: &std::path::Path) -> anyhow::Result<()> {
+        Transaction::remove(self, relative).await
+    }
+
+    async fn is_empty_dir(&self, relative: &std::path::Path) -> anyhow::Result<bool> {
+        let path = self.root.join(relative);
+        Ok(path.is_dir()
+            && fs_err::tokio::read_dir(&path)
+                .await?
+                .next_entry()
+                .await?
+                .is_none())
+    }
+
+    async fn backup_untracked(&mut self, relative: &std::path::Path) -> anyhow::Result<()> {
+        crate::installer::backup_untracked_file(&self.root, relative).await
+    }
+
+    async fn backup_configuration(
+        &mut self,
+        manifest: &crate::models::Manifest,
+        on_file: impl FnMut(usize, usize, &str) -> anyhow::Result<()> + Send,
+    ) -> anyhow::Result<()> {
+        let backup =
+            crate::config_backup::backup_configuration(&self.root, manifest, on_file).await?;
+        if let Some(backup) = backup {
+            log::info!("Backed up configuration of {} to {backup:?}", manifest.id);
+        }
+        Ok(())
+    }
+
+    async fn receipt(
+        &self,
+        id: &crate::models::ManifestId,
+    ) -> anyhow::Result<Option<crate::receipt::Receipt>> {
+        crate::receipt::Receipt::load(&self.root, id).await
+    }
+
+    async fn receipts(&self) -> anyhow::Result<Vec<crate::receipt::Receipt>> {
+        crate::receipt::Receipt::load_all(&self.root).await
+    }
+
+    async fn write_receipt(&mut self, receipt: &crate::receipt::Receipt) -> anyhow::Result<()> {
+        let receipt_path = crate::receipt::Receipt::relative_path(&receipt.id);
+        let staged_receipt = Transaction::staging_dir(self).join(&receipt_path);
+        if let Some(parent) = staged_receipt.parent() {
+            fs_err::tokio::create_dir_all(parent).await?;
+        }
+        fs_err::tokio::write(&staged_receipt, serde_json::to_vec_pretty(receipt)?).await?;
+        self.place(&staged_receipt, &receipt_path).await
+    }
+
+    async fn remove_receipt(&mut self, id: &crate::models::ManifestId) -> anyhow::Result<()> {
+        Transaction::remove(self, &crate::receipt::Receipt::relative_path(id)).await
+    }
+}
+
+fn transactions_dir(root: &std::path::Path) -> std::path::PathBuf {
+    root.join(DATA_DIR).join(TEMP_DIR).join(TRANSACTIONS_DIR)
+}
+
+async fn rollback_journal(
+    root: &std::path::Path,
+    dir: &std::path::Path,
+    journal: &Journal,
+) -> anyhow::Result<()> {
+    for entry in journal.entries.iter().rev() {
+        match entry {
+            JournalEntry::Created { path } => {
+                remove_path(&root.join(path)).await?;
+            }
+            JournalEntry::CreatedDir { path } => {
+                let path = root.join(path);
+                // 他のファイルが置かれている場合は残す
+                if path.is_dir()
+                    && fs_err::tokio::read_dir(&path)
+                        .await?
+                        .next_entry()
+                        .await?
+                        .is_none()
+                {
+                    fs_err::tokio::remove_dir(&path).await?;
+                }
+            }
+            JournalEntry::Replaced { path, backup } | JournalEntry::Removed { path, backup } => {
+                let backup = dir.join(backup);
+                // バックアップが存在しない場合は、移動する前に中断されている
+                if fs_err::tokio::symlink_metadata(&backup).await.is_err() {
+                    continue;
+                }
+                let path = root.join(path);
+                remove_path(&path).await?;
+                move_path(&backup, &path).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn remove_path(path: &std::path::Path) -> anyhow::Result<()> {
+    match fs_err::tokio::symlink_metadata(path).await {
+        Ok(metadata) if metadata.is_dir() => fs_err::tokio::remove_dir_all(path).await?,
+        Ok(_) => fs_err::tokio::remove_file(path).await?,
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+async fn move_path(from: &std::path::Path, to: &std::path::Path) -> anyhow::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs_err::tokio::create_dir_all(parent).await?;
+    }
+    match fs_err::tokio::rename(from, to).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices && from.is_file() => {
+            fs_err::tokio::copy(from, to).await?;
+            fs_err::tokio::remove_file(from).await?;
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rollback_removes_created_dirs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs_err::tokio::create_dir_all(root.join("Script"))
+            .await
+            .unwrap();
+        let id = "test-transaction".parse().unwrap();
+
+        let mut transaction = Transaction::begin(root, &id).await.unwrap();
+        let staged = transaction.staging_dir().join("a.anm2");
+        fs_err::tokio::write(&staged, "a").await.unwrap();
+        transaction
+            .place(&staged, std::path::Path::new("Script/pack/sub/a.anm2"))
+            .await
+            .unwrap();
+        assert!(root.join("Script/pack/sub/a.anm2").exists());
+        transaction.rollback().await.unwrap();
+
+        assert!(!root.join("Script/pack").exists());
+        assert!(root.join("Script").exists());
+    }
+}
@@ -13,6 +13,9 @@ pub fn index_store_path(app: &tauri::AppHandle) -> anyhow::Result<std::path::Pat
 pub fn manifests_dir(app: &tauri::AppHandle) -> anyhow::Result<std::path::PathBuf> {
     Ok(app.path().app_data_dir()?.join("manifests"))
 }
+pub fn cache_dir(app: &tauri::AppHandle) -> anyhow::Result<std::path::PathBuf> {
+    Ok(app.path().app_data_dir()?.join("cache"))
+}
 pub async fn open_index_store(
     app: &tauri::AppHandle,
 ) -> anyhow::Result<crate::store::LockedStore<crate::store::IndexStore>> {
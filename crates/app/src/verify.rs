@@ -0,0 +1,157 @@
+use crate::installer::{hash_file, is_root_type_dir, DataDirPathExt};
+use crate::receipt::{owned_paths, Receipt};
+use futures::StreamExt;
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct VerifyResult {
+    /// 存在しないファイル。
+    pub missing: Vec<std::path::PathBuf>,
+    /// ハッシュ値が一致しないファイル。
+    pub modified: Vec<std::path::PathBuf>,
+    /// インストール先のディレクトリにある、インストールしていないファイル。
+    pub extra: Vec<std::path::PathBuf>,
+}
+
+impl VerifyResult {
+    /// 再インストールが必要なファイル。
+    pub fn broken_files(&self) -> Vec<std::path::PathBuf> {
+        self.missing
+            .iter()
+            .chain(self.modified.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+/// `manifest`でインストールされたファイルが変更されていないかを調べる。
+///
+/// レシートがある場合はそれを元に全てのファイルを調べます。
+/// ない場合はマニフェストの`resources`のうち、ファイルのものだけを調べます。
+pub async fn verify_manifest(
+    root: &std::path::Path,
+    manifest: &crate::models::Manifest,
+) -> anyhow::Result<VerifyResult> {
+    let mut result = VerifyResult::default();
+    let receipt = Receipt::load(root, &manifest.id).await?;
+
+    let expected: Vec<(std::path::PathBuf, Option<String>)> = match &receipt {
+        Some(receipt) => receipt
+            .files
+            .iter()
+            .map(|f| (f.path.as_relative_path(), Some(f.sha256.clone())))
+            .collect(),
+        None => manifest
+            .resources
+            .iter()
+            .map(|r| (r.destination.as_relative_path(), r.sha256.clone()))
+            .collect(),
+    };
+    for (path, sha256) in &expected {
+        let abs_path = root.join(path);
+        if fs_err::tokio::metadata(&abs_path).await.is_err() {
+            result.missing.push(path.clone());
+            continue;
+        }
+        let Some(sha256) = sha256 else {
+            continue;
+        };
+        if abs_path.is_file() && !hash_file(&abs_path).await?.1.eq_ignore_ascii_case(sha256) {
+            result.modified.push(path.clone());
+        }
+    }
+
+    if receipt.is_some() {
+        let owned_paths = owned_paths(root, std::slice::from_ref(manifest)).await?;
+        for resource in &manifest.resources {
+            if !resource.destination.is_directory() {
+                continue;
+            }
+            let relative_path = resource.destination.as_relative_path();
+            if is_root_type_dir(&relative_path) {
+                continue;
+            }
+            let abs_dir = root.join(&relative_path);
+            if !abs_dir.is_dir() {
+                continue;
+            }
+            let mut walk = async_walkdir::WalkDir::new(&abs_dir);
+            while let Some(entry) = walk.next().await {
+                let entry = entry?;
+                if !entry.file_type().await?.is_file() {
+                    continue;
+                }
+                let path = entry.path().strip_prefix(root)?.to_path_buf();
+                if !owned_paths.iter().any(|owned| path.starts_with(owned))
+                    && !result.extra.contains(&path)
+                {
+                    result.extra.push(path);
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AsVersion;
+    use crate::receipt::ReceiptFile;
+
+    #[tokio::test]
+    async fn test_configurations_are_not_extra() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let manifest: crate::models::Manifest = serde_json::from_value(serde_json::json!({
+            "manifest_version": 1,
+            "id": "test-verify",
+            "name": "test-verify",
+            "summary": "",
+            "version": "1.0.0",
+            "authors": [{ "name": "test" }],
+            "license": { "name": "free" },
+            "resources": [{ "source": "https://example.com/test.zip", "destination": "$plugin/test/" }],
+            "configurations": [{ "path": "$plugin/test/config.ini" }],
+            "disposables": [{ "path": "$plugin/test/cache/" }],
+        }))
+        .unwrap();
+        let dir = root.join("Plugin/test");
+        fs_err::tokio::create_dir_all(dir.join("cache"))
+            .await
+            .unwrap();
+        for (name, content) in [
+            ("test.auf2", "plugin"),
+            ("config.ini", "a=1"),
+            ("cache/data.bin", "cache"),
+            ("extra.txt", "extra"),
+        ] {
+            fs_err::tokio::write(dir.join(name), content).await.unwrap();
+        }
+        let receipt = Receipt {
+            id: manifest.id.clone(),
+            version: manifest.as_version(),
+            files: vec![ReceiptFile {
+                path: serde_json::from_value(serde_json::json!("$plugin/test/test.auf2")).unwrap(),
+                scope: None,
+                size: 6,
+                sha256: hash_file(&dir.join("test.auf2")).await.unwrap().1,
+            }],
+        };
+        let receipt_path = root.join(Receipt::relative_path(&manifest.id));
+        fs_err::tokio::create_dir_all(receipt_path.parent().unwrap())
+            .await
+            .unwrap();
+        fs_err::tokio::write(&receipt_path, serde_json::to_vec(&receipt).unwrap())
+            .await
+            .unwrap();
+
+        let result = verify_manifest(root, &manifest).await.unwrap();
+        assert!(result.missing.is_empty());
+        assert!(result.modified.is_empty());
+        assert_eq!(
+            result.extra,
+            [std::path::PathBuf::from("Plugin/test/extra.txt")]
+        );
+    }
+}
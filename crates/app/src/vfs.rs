@@ -0,0 +1,423 @@
+use crate::installer::{list_files_blocking, DataDirPathExt};
+
+/// ドライランで行われる、1つのファイルへの操作。パスはデータディレクトリからの相対パス。
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FileOperation {
+    Create {
+        manifest_id: crate::models::ManifestId,
+        path: std::path::PathBuf,
+        /// 書き込むファイルの大きさ。ダウンロードするまで分からない場合は`None`。
+        size: Option<u64>,
+    },
+    Overwrite {
+        manifest_id: crate::models::ManifestId,
+        path: std::path::PathBuf,
+        size: Option<u64>,
+        previous_size: Option<u64>,
+    },
+    Delete {
+        manifest_id: crate::models::ManifestId,
+        path: std::path::PathBuf,
+        size: Option<u64>,
+    },
+    Backup {
+        manifest_id: crate::models::ManifestId,
+        path: std::path::PathBuf,
+        size: Option<u64>,
+    },
+}
+
+/// 配置するファイル。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedFile {
+    /// 取り出したファイルのパス。ファイルを取り出さないドライランでは`None`。
+    pub staged: Option<std::path::PathBuf>,
+    pub path: crate::models::DataDirRelativePath,
+    /// ファイルの大きさ。取り出していない場合に、キャッシュなどから分かるもの。
+    pub size: Option<u64>,
+    /// ファイルのSHA256ハッシュ値。取り出していない場合に、マニフェストから分かるもの。
+    pub sha256: Option<String>,
+}
+
+impl StagedFile {
+    /// ファイルの大きさとSHA256ハッシュ値。
+    /// 取り出していない場合は分かっている値を返し、分からないものは空になります。
+    pub async fn hash(&self) -> std::io::Result<(u64, String)> {
+        match &self.staged {
+            Some(staged) => crate::installer::hash_file(staged).await,
+            None => Ok((
+                self.size.unwrap_or_default(),
+                self.sha256.clone().unwrap_or_default(),
+            )),
+        }
+    }
+}
+
+/// インストールやアンインストールで、データディレクトリを変更する先。
+///
+/// 実際に変更する[`crate::transaction::Transaction`]と、変更を記録するだけの[`VirtualFs`]があり、
+/// `InstallPlan::perform`と`InstallPlan::dry_run`は同じ処理をそれぞれに対して行います。
+pub trait FileSystem {
+    /// データディレクトリ。
+    fn root(&self) -> &std::path::Path;
+
+    /// インストールするファイルを取り出すディレクトリ。ファイルを取り出さない場合は`None`。
+    fn staging_dir(&self) -> Option<std::path::PathBuf>;
+
+    /// `file`を配置する。既にファイルがある場合は上書きする。
+    async fn place_file(&mut self, file: &StagedFile) -> anyhow::Result<()>;
+
+    /// `relative`（データディレクトリからの相対パス）以下を削除する。
+    async fn remove(&mut self, relative: &std::path::Path) -> anyhow::Result<()>;
+
+    /// `relative`が空のディレクトリかどうか。
+    async fn is_empty_dir(&self, relative: &std::path::Path) -> anyhow::Result<bool>;
+
+    /// 上書きされる管理外のファイルをバックアップする。
+    async fn backup_untracked(&mut self, relative: &std::path::Path) -> anyhow::Result<()>;
+
+    /// アンインストールする`manifest`の設定ファイルをバックアップする。
+    async fn backup_configuration(
+        &mut self,
+        manifest: &crate::models::Manifest,
+        on_file: impl FnMut(usize, usize, &str) -> anyhow::Result<()> + Send,
+    ) -> anyhow::Result<()>;
+
+    async fn receipt(
+        &self,
+        id: &crate::models::ManifestId,
+    ) -> anyhow::Result<Option<crate::receipt::Receipt>>;
+
+    /// 全てのインストール済みのマニフェストのレシート。
+    async fn receipts(&self) -> anyhow::Result<Vec<crate::receipt::Receipt>>;
+
+    async fn write_receipt(&mut self, receipt: &crate::receipt::Receipt) -> anyhow::Result<()>;
+
+    async fn remove_receipt(&mut self, id: &crate::models::ManifestId) -> anyhow::Result<()>;
+}
+
+/// マニフェストごとに[`FileSystem`]を用意して変更を行うもの。
+pub trait Workspace {
+    type Fs: FileSystem;
+
+    /// `f`で`manifest_id`の変更を行う。失敗した場合は、その変更を全て取り消す。
+    async fn run(
+        &mut self,
+        manifest_id: &crate::models::ManifestId,
+        f: impl AsyncFnOnce(&mut Self::Fs) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Written { size: Option<u64> },
+    Removed,
+}
+
+/// ディスクは読むだけにして、変更をメモリ上に記録するファイルシステム。
+///
+/// 記録した変更は後の読み取りに反映されるので、同じファイルを削除してから書き込んだ場合は
+/// 上書きではなく作成として記録されます。
+#[derive(Debug)]
+pub struct VirtualFs {
+    root: std::path::PathBuf,
+    /// 変更しているマニフェスト。
+    manifest_id: Option<crate::models::ManifestId>,
+    changes: std::collections::HashMap<std::path::PathBuf, Change>,
+    /// 書き込んだレシート。削除した場合は`None`。
+    receipts: std::collections::HashMap<crate::models::ManifestId, Option<crate::receipt::Receipt>>,
+    operations: Vec<FileOperation>,
+    /// 失敗したマニフェストのうち、最初のもののエラー。
+    error: Option<anyhow::Error>,
+}
+
+impl VirtualFs {
+    pub fn new(root: &std::path::Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            manifest_id: None,
+            changes: std::collections::HashMap::new(),
+            receipts: std::collections::HashMap::new(),
+            operations: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn manifest_id(&self) -> anyhow::Result<crate::models::ManifestId> {
+        self.manifest_id
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No manifest is being changed"))
+    }
+
+    /// `relative`にファイルがある場合、その大きさ（分からない場合は`None`）を返す。
+    async fn stat(&self, relative: &std::path::Path) -> Option<Option<u64>> {
+        match self.changes.get(relative) {
+            Some(Change::Written { size }) => Some(*size),
+            Some(Change::Removed) => None,
+            None => fs_err::tokio::metadata(self.root.join(relative))
+                .await
+                .ok()
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| Some(metadata.len())),
+        }
+    }
+
+    /// `relative`以下のファイル。`relative`がファイルの場合はそれ自身。
+    pub async fn files(
+        &self,
+        relative: &std::path::Path,
+    ) -> anyhow::Result<Vec<std::path::PathBuf>> {
+        let root = self.root.clone();
+        let path = root.join(relative);
+        let on_disk = tokio::task::spawn_blocking(move || list_files_blocking(&path)).await??;
+        let mut files = on_disk
+            .into_iter()
+            .filter_map(|path| path.strip_prefix(&root).ok().map(|path| path.to_path_buf()))
+            .collect::<std::collections::BTreeSet<_>>();
+        for (path, change) in &self.changes {
+            if !path.starts_with(relative) {
+                continue;
+            }
+            match change {
+                Change::Written { .. } => files.insert(path.clone()),
+                Change::Removed => files.remove(path),
+            };
+        }
+        Ok(files.into_iter().collect())
+    }
+
+    /// `relative`以下のファイルをバックアップする。
+    async fn backup(&mut self, relative: &std::path::Path) -> anyhow::Result<()> {
+        let manifest_id = self.manifest_id()?;
+        for path in self.files(relative).await? {
+            let size = self.stat(&path).await.flatten();
+            self.operations.push(FileOperation::Backup {
+                manifest_id: manifest_id.clone(),
+                path,
+                size,
+            });
+        }
+        Ok(())
+    }
+
+    /// 記録したファイル操作の一覧。失敗したマニフェストがある場合はそのエラーを返す。
+    pub fn into_operations(self) -> anyhow::Result<Vec<FileOperation>> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.operations),
+        }
+    }
+}
+
+impl FileSystem for VirtualFs {
+    fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    fn staging_dir(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+
+    async fn place_file(&mut self, file: &StagedFile) -> anyhow::Result<()> {
+        let manifest_id = self.manifest_id()?;
+        let relative = file.path.as_relative_path();
+        let operation = match self.stat(&relative).await {
+            Some(previous_size) => FileOperation::Overwrite {
+                manifest_id,
+                path: relative.clone(),
+                size: file.size,
+                previous_size,
+            },
+            None => FileOperation::Create {
+                manifest_id,
+                path: relative.clone(),
+                size: file.size,
+            },
+        };
+        self.operations.push(operation);
+        self.changes
+            .insert(relative, Change::Written { size: file.size });
+        Ok(())
+    }
+
+    async fn remove(&mut self, relative: &std::path::Path) -> anyhow::Result<()> {
+        let manifest_id = self.manifest_id()?;
+        for path in self.files(relative).await? {
+            let size = self.stat(&path).await.flatten();
+            self.operations.push(FileOperation::Delete {
+                manifest_id: manifest_id.clone(),
+                path: path.clone(),
+                size,
+            });
+            self.changes.insert(path, Change::Removed);
+        }
+        Ok(())
+    }
+
+    async fn is_empty_dir(&self, relative: &std::path::Path) -> anyhow::Result<bool> {
+        Ok(self.files(relative).await?.is_empty())
+    }
+
+    async fn backup_untracked(&mut self, relative: &std::path::Path) -> anyhow::Result<()> {
+        self.backup(relative).await
+    }
+
+    async fn backup_configuration(
+        &mut self,
+        manifest: &crate::models::Manifest,
+        _on_file: impl FnMut(usize, usize, &str) -> anyhow::Result<()> + Send,
+    ) -> anyhow::Result<()> {
+        for config in &manifest.configurations {
+            self.backup(&config.path.as_relative_path()).await?;
+        }
+        Ok(())
+    }
+
+    async fn receipt(
+        &self,
+        id: &crate::models::ManifestId,
+    ) -> anyhow::Result<Option<crate::receipt::Receipt>> {
+        match self.receipts.get(id) {
+            Some(receipt) => Ok(receipt.clone()),
+            None => crate::receipt::Receipt::load(&self.root, id).await,
+        }
+    }
+
+    async fn receipts(&self) -> anyhow::Result<Vec<crate::receipt::Receipt>> {
+        let mut receipts = crate::receipt::Receipt::load_all(&self.root)
+            .await?
+            .into_iter()
+            .filter(|receipt| !self.receipts.contains_key(&receipt.id))
+            .collect::<Vec<_>>();
+        receipts.extend(self.receipts.values().flatten().cloned());
+        Ok(receipts)
+    }
+
+    async fn write_receipt(&mut self, receipt: &crate::receipt::Receipt) -> anyhow::Result<()> {
+        self.receipts
+            .insert(receipt.id.clone(), Some(receipt.clone()));
+        Ok(())
+    }
+
+    async fn remove_receipt(&mut self, id: &crate::models::ManifestId) -> anyhow::Result<()> {
+        self.receipts.insert(id.clone(), None);
+        Ok(())
+    }
+}
+
+impl Workspace for VirtualFs {
+    type Fs = Self;
+
+    async fn run(
+        &mut self,
+        manifest_id: &crate::models::ManifestId,
+        f: impl AsyncFnOnce(&mut Self) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let changes = self.changes.clone();
+        let receipts = self.receipts.clone();
+        let num_operations = self.operations.len();
+        self.manifest_id = Some(manifest_id.clone());
+        let result = f(self).await;
+        self.manifest_id = None;
+        if let Err(e) = &result {
+            self.changes = changes;
+            self.receipts = receipts;
+            self.operations.truncate(num_operations);
+            self.error.get_or_insert_with(|| anyhow::anyhow!("{e}"));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_virtual_fs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs_err::tokio::create_dir_all(root.join("Script/pack"))
+            .await
+            .unwrap();
+        fs_err::tokio::write(root.join("Script/pack/a.anm2"), "aaa")
+            .await
+            .unwrap();
+        fs_err::tokio::write(root.join("Script/pack/b.anm2"), "bb")
+            .await
+            .unwrap();
+        let id = "test-a".parse::<crate::models::ManifestId>().unwrap();
+        let path = std::path::Path::new;
+
+        let mut fs = VirtualFs::new(root);
+        let staged = |path: &str, size| StagedFile {
+            staged: None,
+            path: serde_json::from_value(serde_json::json!(path)).unwrap(),
+            size,
+            sha256: None,
+        };
+        fs.run(&id, async |fs| {
+            fs.place_file(&staged("$script/pack/a.anm2", Some(1)))
+                .await?;
+            fs.remove(path("Script/pack")).await?;
+            fs.place_file(&staged("$script/pack/b.anm2", None)).await?;
+            fs.backup_untracked(path("Script/pack")).await
+        })
+        .await
+        .unwrap();
+        // 失敗したマニフェストの変更は取り消される
+        let other = "test-b".parse::<crate::models::ManifestId>().unwrap();
+        let result = fs
+            .run(&other, async |fs| {
+                fs.remove(path("Script/pack")).await?;
+                anyhow::bail!("failed")
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            fs.files(path("Script/pack")).await.unwrap(),
+            [path("Script/pack/b.anm2")]
+        );
+        let error = fs.error.take().unwrap();
+        assert_eq!(error.to_string(), "failed");
+        assert_eq!(
+            fs.into_operations().unwrap(),
+            [
+                FileOperation::Overwrite {
+                    manifest_id: id.clone(),
+                    path: path("Script/pack/a.anm2").to_path_buf(),
+                    size: Some(1),
+                    previous_size: Some(3),
+                },
+                FileOperation::Delete {
+                    manifest_id: id.clone(),
+                    path: path("Script/pack/a.anm2").to_path_buf(),
+                    size: Some(1),
+                },
+                FileOperation::Delete {
+                    manifest_id: id.clone(),
+                    path: path("Script/pack/b.anm2").to_path_buf(),
+                    size: Some(2),
+                },
+                FileOperation::Create {
+                    manifest_id: id.clone(),
+                    path: path("Script/pack/b.anm2").to_path_buf(),
+                    size: None,
+                },
+                FileOperation::Backup {
+                    manifest_id: id.clone(),
+                    path: path("Script/pack/b.anm2").to_path_buf(),
+                    size: None,
+                },
+            ]
+        );
+        // ディスクには何も書き込まれていない
+        assert_eq!(
+            fs_err::tokio::read(root.join("Script/pack/a.anm2"))
+                .await
+                .unwrap(),
+            b"aaa"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use crate::download::{fetch_range, DownloadProgress};
+use tokio::io::AsyncWriteExt;
+
+/// 最初に末尾から取得する大きさ。EOCD（22バイト）と最大の長さのコメント、
+/// Zip64のEOCDとロケーター（76バイト）が収まるようにする。
+const TAIL_LEN: u64 = 22 + 0xFFFF + 76;
+
+const EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+const ZIP64_EOCD_SIGNATURE: &[u8] = b"PK\x06\x06";
+const ZIP64_LOCATOR_SIGNATURE: &[u8] = b"PK\x06\x07";
+const CENTRAL_RECORD_SIGNATURE: &[u8] = b"PK\x01\x02";
+
+/// 中央ディレクトリの位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CentralDirectory {
+    offset: u64,
+    size: u64,
+}
+
+/// 中央ディレクトリの1つのレコード。
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CentralRecord {
+    /// 中央ディレクトリ内での範囲。
+    range: std::ops::Range<usize>,
+    header_offset: u64,
+    /// ローカルヘッダーの位置が書かれている場所（レコードの先頭から）と、その大きさ。
+    offset_field: (usize, usize),
+}
+
+/// zipの`url`から、`select`が選んだエントリだけを含むzipを`dest`に作る。
+///
+/// 中央ディレクトリを`Range`リクエストで読み、選ばれたエントリの範囲だけをダウンロードします。
+/// サーバーが`Range`に対応していない、zipではない、または半分以上をダウンロードする必要がある場合は
+/// 何もせずに`false`を返します。
+pub async fn download_entries(
+    client: &reqwest::Client,
+    url: &url::Url,
+    dest: &std::path::Path,
+    filename_encoding: Option<&'static encoding_rs::Encoding>,
+    select: impl FnOnce(&[String]) -> anyhow::Result<std::collections::HashSet<String>>,
+    mut on_progress: impl FnMut(DownloadProgress) -> anyhow::Result<()>,
+) -> anyhow::Result<bool> {
+    let Some(tail) = fetch_range(client, url, &format!("-{TAIL_LEN}"), None).await? else {
+        return Ok(false);
+    };
+    let (tail_start, total) = (tail.start, tail.total);
+    let validator = tail.validator.clone();
+    let validator = validator.as_deref();
+    let tail = tail.bytes().await?;
+    let Some(directory) = find_central_directory(tail_start, &tail)? else {
+        return Ok(false);
+    };
+    // NOTE: find_central_directoryで中央ディレクトリの終わりが末尾の範囲内にあることは確認済み
+    let tail_end = usize::try_from(directory.offset + directory.size - tail_start)?;
+    let cd = if directory.offset >= tail_start {
+        let start = usize::try_from(directory.offset - tail_start)?;
+        tail.get(start..tail_end)
+            .ok_or_else(|| anyhow::anyhow!("Invalid central directory position: {url}"))?
+            .to_vec()
+    } else {
+        let Some(head) = fetch_range(
+            client,
+            url,
+            &format!("{}-{}", directory.offset, tail_start - 1),
+            validator,
+        )
+        .await?
+        else {
+            return Ok(false);
+        };
+        anyhow::ensure!(
+            head.start == directory.offset,
+            "Unexpected range response: {url}"
+        );
+        let mut cd = head.bytes().await?;
+        anyhow::ensure!(
+            cd.len() as u64 == tail_start - directory.offset,
+            "Unexpected range response: {url}"
+        );
+        cd.extend_from_slice(&tail[..tail_end]);
+        cd
+    };
+    let records = parse_central_directory(&cd)?;
+
+    // NOTE: ファイル名は展開時と同じようにasync_zipで読む
+    let mut listing = cd.clone();
+    write_end_of_central_directory(&mut listing, records.len() as u64, cd.len() as u64, 0);
+    let zip = async_zip::base::read::mem::ZipFileReader::new(listing).await?;
+    let names = zip
+        .file()
+        .entries()
+        .iter()
+        .map(|e| crate::archive::decode_zip_filename(e.filename(), filename_encoding))
+        .collect::<Vec<_>>();
+    anyhow::ensure!(
+        names.len() == records.len(),
+        "Mismatched number of central directory records: {url}"
+    );
+    let files = names
+        .iter()
+        .filter(|name| !name.ends_with('/'))
+        .cloned()
+        .collect::<Vec<_>>();
+    let wanted = select(&files)?;
+
+    // NOTE: エントリの終わりは、次のエントリの始まりか中央ディレクトリの始まりとする
+    let mut boundaries = records
+        .iter()
+        .map(|record| record.header_offset)
+        .chain([directory.offset])
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    let mut spans = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        if !wanted.contains(&names[index]) {
+            continue;
+        }
+        let end = boundaries
+            .get(boundaries.partition_point(|&offset| offset <= record.header_offset))
+            .ok_or_else(|| anyhow::anyhow!("Invalid local header offset: {url}"))?;
+        spans.push((record.header_offset, *end, index));
+    }
+    spans.sort_unstable();
+    let wanted_bytes = spans.iter().map(|(start, end, _)| end - start).sum::<u64>();
+    if wanted_bytes * 2 >= total {
+        return Ok(false);
+    }
+
+    let mut part_path = dest.as_os_str().to_owned();
+    part_path.push(".ranges");
+    let part_path = std::path::PathBuf::from(part_path);
+    let mut file = fs_err::tokio::File::create(&part_path).await?;
+    let mut new_offsets = std::collections::HashMap::new();
+    let mut written = 0u64;
+    on_progress(DownloadProgress {
+        bytes: 0,
+        total_bytes: Some(wanted_bytes),
+        resumed_from: 0,
+    })?;
+    // 隣り合うエントリはまとめて取得する
+    for group in spans.chunk_by(|(_, end, _), (start, _, _)| end == start) {
+        let start = group[0].0;
+        let end = group[group.len() - 1].1;
+        let Some(range) =
+            fetch_range(client, url, &format!("{start}-{}", end - 1), validator).await?
+        else {
+            drop(file);
+            fs_err::tokio::remove_file(&part_path).await?;
+            return Ok(false);
+        };
+        anyhow::ensure!(range.start == start, "Unexpected range response: {url}");
+        let len = range
+            .write_to(&mut file, end - start, |len| {
+                on_progress(DownloadProgress {
+                    bytes: written + len,
+                    total_bytes: Some(wanted_bytes),
+                    resumed_from: 0,
+                })
+            })
+            .await?;
+        anyhow::ensure!(len == end - start, "Unexpected range response: {url}");
+        for (entry_start, _, index) in group {
+            new_offsets.insert(*index, written + (entry_start - start));
+        }
+        written += len;
+    }
+
+    let mut new_cd = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        let Some(new_offset) = new_offsets.get(&index) else {
+            continue;
+        };
+        let mut bytes = cd[record.range.clone()].to_vec();
+        let (at, len) = record.offset_field;
+        bytes[at..at + len].copy_from_slice(&new_offset.to_le_bytes()[..len]);
+        new_cd.extend_from_slice(&bytes);
+    }
+    let cd_size = new_cd.len() as u64;
+    write_end_of_central_directory(&mut new_cd, new_offsets.len() as u64, cd_size, written);
+    file.write_all(&new_cd).await?;
+    file.flush().await?;
+    drop(file);
+    fs_err::tokio::rename(&part_path, dest).await?;
+    Ok(true)
+}
+
+/// 末尾`tail`（ファイル内の位置は`tail_start`から）から中央ディレクトリの位置を探す。
+/// 中央ディレクトリの終端が見つからない場合は`None`を返す。
+fn find_central_directory(
+    tail_start: u64,
+    tail: &[u8],
+) -> anyhow::Result<Option<CentralDirectory>> {
+    let Some(eocd) = (0..tail.len().saturating_sub(21)).rev().find(|&i| {
+        tail[i..].starts_with(EOCD_SIGNATURE)
+            && read_le(tail, i + 20, 2).is_ok_and(|len| i + 22 + len as usize <= tail.len())
+    }) else {
+        return Ok(None);
+    };
+    let entries = read_le(tail, eocd + 10, 2)?;
+    let mut size = read_le(tail, eocd + 12, 4)?;
+    let mut offset = read_le(tail, eocd + 16, 4)?;
+    if entries == 0xFFFF || size == 0xFFFF_FFFF || offset == 0xFFFF_FFFF {
+        let Some(locator) = eocd.checked_sub(20) else {
+            return Ok(None);
+        };
+        anyhow::ensure!(
+            tail[locator..].starts_with(ZIP64_LOCATOR_SIGNATURE),
+            "Zip64 end of central directory locator not found"
+        );
+        let Some(zip64_eocd) = read_le(tail, locator + 8, 8)?
+            .checked_sub(tail_start)
+            .map(|offset| offset as usize)
+        else {
+            return Ok(None);
+        };
+        anyhow::ensure!(
+            tail.get(zip64_eocd..)
+                .is_some_and(|eocd| eocd.starts_with(ZIP64_EOCD_SIGNATURE)),
+            "Zip64 end of central directory not found"
+        );
+        size = read_le(tail, zip64_eocd + 40, 8)?;
+        offset = read_le(tail, zip64_eocd + 48, 8)?;
+    }
+    // NOTE: 中央ディレクトリの終わりが末尾の範囲より前にある場合は壊れているので、全体をダウンロードさせる
+    let Some(end) = offset.checked_add(size).filter(|&end| end >= tail_start) else {
+        return Ok(None);
+    };
+    if end > tail_start + tail.len() as u64 {
+        anyhow::bail!("Invalid central directory position");
+    }
+    Ok(Some(CentralDirectory { offset, size }))
+}
+
+fn parse_central_directory(cd: &[u8]) -> anyhow::Result<Vec<CentralRecord>> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while pos < cd.len() {
+        anyhow::ensure!(
+            cd[pos..].starts_with(CENTRAL_RECORD_SIGNATURE),
+            "Invalid central directory record"
+        );
+        let name_len = read_le(cd, pos + 28, 2)? as usize;
+        let extra_len = read_le(cd, pos + 30, 2)? as usize;
+        let comment_len = read_le(cd, pos + 32, 2)? as usize;
+        let end = pos + 46 + name_len + extra_len + comment_len;
+        anyhow::ensure!(end <= cd.len(), "Invalid central directory record");
+
+        let mut header_offset = read_le(cd, pos + 42, 4)?;
+        let mut offset_field = (42, 4);
+        if header_offset == 0xFFFF_FFFF {
+            let mut extra = pos + 46 + name_len;
+            let extra_end = extra + extra_len;
+            let mut found = false;
+            while extra + 4 <= extra_end {
+                let id = read_le(cd, extra, 2)?;
+                let size = read_le(cd, extra + 2, 2)? as usize;
+                if id == 0x0001 {
+                    // NOTE: Zip64拡張情報には、0xFFFFFFFFになっている値だけが元のサイズ、圧縮後のサイズ、
+                    // ローカルヘッダーの位置の順に入っている
+                    let mut field = extra + 4;
+                    for at in [24, 20] {
+                        if read_le(cd, pos + at, 4)? == 0xFFFF_FFFF {
+                            field += 8;
+                        }
+                    }
+                    header_offset = read_le(cd, field, 8)?;
+                    offset_field = (field - pos, 8);
+                    found = true;
+                    break;
+                }
+                extra += 4 + size;
+            }
+            anyhow::ensure!(found, "Zip64 extended information not found");
+        }
+        records.push(CentralRecord {
+            range: pos..end,
+            header_offset,
+            offset_field,
+        });
+        pos = end;
+    }
+    Ok(records)
+}
+
+/// `out`に中央ディレクトリの終端を書き込む。必要な場合はZip64の終端も書き込む。
+fn write_end_of_central_directory(out: &mut Vec<u8>, entries: u64, cd_size: u64, cd_offset: u64) {
+    if entries >= 0xFFFF || cd_size >= 0xFFFF_FFFF || cd_offset >= 0xFFFF_FFFF {
+        let zip64_eocd = cd_offset + cd_size;
+        out.extend_from_slice(ZIP64_EOCD_SIGNATURE);
+        out.extend_from_slice(&44u64.to_le_bytes());
+        out.extend_from_slice(&45u16.to_le_bytes());
+        out.extend_from_slice(&45u16.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&entries.to_le_bytes());
+        out.extend_from_slice(&entries.to_le_bytes());
+        out.extend_from_slice(&cd_size.to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+
+        out.extend_from_slice(ZIP64_LOCATOR_SIGNATURE);
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&zip64_eocd.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+    }
+    out.extend_from_slice(EOCD_SIGNATURE);
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&(entries.min(0xFFFF) as u16).to_le_bytes());
+    out.extend_from_slice(&(entries.min(0xFFFF) as u16).to_le_bytes());
+    out.extend_from_slice(&(cd_size.min(0xFFFF_FFFF) as u32).to_le_bytes());
+    out.extend_from_slice(&(cd_offset.min(0xFFFF_FFFF) as u32).to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+}
+
+/// `buf`の`at`から`len`バイトをリトルエンディアンの整数として読む。
+fn read_le(buf: &[u8], at: usize, len: usize) -> anyhow::Result<u64> {
+    let bytes = buf
+        .get(at..at + len)
+        .ok_or_else(|| anyhow::anyhow!("Unexpected end of zip data"))?;
+    Ok(bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8) | byte as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    /// `body`を返すサーバー。`supports_range`が`true`の場合は`Range`に従って一部を返す。
+    async fn range_server(body: Vec<u8>, supports_range: bool) -> url::Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = url::Url::parse(&format!(
+            "http://{}/bundle.zip",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap().to_ascii_lowercase();
+                let range = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .filter(|_| supports_range)
+                    .map(|range| {
+                        let (start, end) = range.trim().split_once('-').unwrap();
+                        match start {
+                            "" => body.len().saturating_sub(end.parse().unwrap())..body.len(),
+                            start => start.parse().unwrap()..end.parse::<usize>().unwrap() + 1,
+                        }
+                    });
+                let mut response = match &range {
+                    Some(range) => format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+                        range.len(),
+                        range.start,
+                        range.end - 1,
+                        body.len()
+                    ),
+                    None => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    ),
+                }
+                .into_bytes();
+                response.extend_from_slice(&body[range.unwrap_or(0..body.len())]);
+                socket.write_all(&response).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[rstest::rstest]
+    #[case::supported(true)]
+    #[case::not_supported(false)]
+    #[tokio::test]
+    async fn test_download_entries(#[case] supports_range: bool) {
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/utf8.zip");
+        let url = range_server(fs_err::read(&fixture).unwrap(), supports_range).await;
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs_err::tokio::create_dir_all(&dir).await.unwrap();
+        let dest = dir.join("bundle.zip");
+
+        let downloaded = download_entries(
+            &reqwest::Client::new(),
+            &url,
+            &dest,
+            None,
+            |names| {
+                assert_eq!(names.len(), 3);
+                Ok(std::collections::HashSet::from(["ソ.txt".to_owned()]))
+            },
+            |_| Ok(()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(downloaded, supports_range);
+
+        if supports_range {
+            let options = crate::archive::OpenOptions::default();
+            let mut extractor = crate::archive::open(&dest, &options).await.unwrap();
+            assert_eq!(extractor.list().await.unwrap(), ["ソ.txt"]);
+            let partial = dir.join("partial.txt");
+            extractor
+                .extract(&crate::archive::ExtractTargets::from([(
+                    "ソ.txt".to_owned(),
+                    vec![partial.clone()],
+                )]))
+                .await
+                .unwrap();
+            let full = dir.join("full.txt");
+            crate::archive::open(&fixture, &options)
+                .await
+                .unwrap()
+                .extract(&crate::archive::ExtractTargets::from([(
+                    "ソ.txt".to_owned(),
+                    vec![full.clone()],
+                )]))
+                .await
+                .unwrap();
+            assert_eq!(fs_err::read(partial).unwrap(), fs_err::read(full).unwrap());
+        } else {
+            assert!(!dest.exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_entries_corrupt_eocd() {
+        let fixture =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/utf8.zip");
+        let mut body = fs_err::read(&fixture).unwrap();
+        // 先頭を伸ばして大きさを0にし、中央ディレクトリが末尾の範囲より前で終わるようにする
+        let eocd = body
+            .windows(4)
+            .rposition(|window| window == EOCD_SIGNATURE)
+            .unwrap();
+        body[eocd + 12..eocd + 16].copy_from_slice(&0u32.to_le_bytes());
+        body.splice(0..0, vec![0u8; TAIL_LEN as usize]);
+        let url = range_server(body, true).await;
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs_err::tokio::create_dir_all(&dir).await.unwrap();
+        let dest = dir.join("bundle.zip");
+
+        let downloaded = download_entries(
+            &reqwest::Client::new(),
+            &url,
+            &dest,
+            None,
+            |_| unreachable!(),
+            |_| Ok(()),
+        )
+        .await
+        .unwrap();
+        assert!(!downloaded);
+        assert!(!dest.exists());
+    }
+}
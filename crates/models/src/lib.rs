@@ -8,6 +8,11 @@ impl PartialEq for DataDirRelativePath {
     }
 }
 impl Eq for DataDirRelativePath {}
+impl std::hash::Hash for DataDirRelativePath {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
 impl std::fmt::Display for DataDirRelativePath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)?;
@@ -28,9 +33,34 @@ impl std::fmt::Display for ManifestId {
     }
 }
 
+impl Bundle {
+    pub fn url(&self) -> &SourceUrl {
+        match self {
+            Bundle::SourceUrl(url) => url,
+            Bundle::BundleDefinition(definition) => &definition.url,
+        }
+    }
+
+    pub fn sha256(&self) -> Option<&str> {
+        match self {
+            Bundle::SourceUrl(_) => None,
+            Bundle::BundleDefinition(definition) => definition.sha256.as_deref(),
+        }
+    }
+
+    pub fn encoding(&self) -> Option<&str> {
+        match self {
+            Bundle::SourceUrl(_) => None,
+            Bundle::BundleDefinition(definition) => definition.encoding.as_deref(),
+        }
+    }
+}
+
 pub use overrides::*;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, strum::Display, strum::EnumString, strum::EnumIter,
+)]
 pub enum RootType {
     #[strum(to_string = "$alias")]
     Alias,
@@ -57,7 +87,7 @@ mod overrides {
     #[allow(non_camel_case_types)]
     pub type u64 = ::std::primitive::u64;
 
-    pub type Bundles = std::collections::BTreeMap<BundleId, HttpUrl>;
+    pub type Bundles = std::collections::BTreeMap<BundleId, super::Bundle>;
 
     #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
     pub struct BundleId(pub String);
@@ -159,7 +189,7 @@ mod overrides {
         }
     }
 
-    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct DataDirRelativePathInner {
         pub root: RootType,
         pub path: String,
@@ -168,6 +198,19 @@ mod overrides {
         pub fn is_directory(&self) -> bool {
             self.path.ends_with('/')
         }
+
+        /// このパスをディレクトリとみなして、`path`を結合する。
+        pub fn join(&self, path: &str) -> Self {
+            let path = if self.is_directory() {
+                format!("{}{}", self.path, path)
+            } else {
+                format!("{}/{}", self.path, path)
+            };
+            Self {
+                root: self.root,
+                path,
+            }
+        }
     }
     impl serde::Serialize for DataDirRelativePathInner {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -30,6 +30,95 @@ pub mod error {
         }
     }
 }
+/**バンドルの取得元。URLを直接指定するか、オブジェクトで詳細を指定します。
+`bundle://{bundle_name}/{path}`を指定すると、他のバンドル内のアーカイブをバンドルとして使えます。*/
+///
+/// <details><summary>JSON schema</summary>
+///
+/// ```json
+///{
+///  "description": "バンドルの取得元。URLを直接指定するか、オブジェクトで詳細を指定します。\n`bundle://{bundle_name}/{path}`を指定すると、他のバンドル内のアーカイブをバンドルとして使えます。",
+///  "anyOf": [
+///    {
+///      "title": "Url",
+///      "$ref": "#/$defs/SourceUrl"
+///    },
+///    {
+///      "title": "Detailed",
+///      "$ref": "#/$defs/BundleDefinition"
+///    }
+///  ]
+///}
+/// ```
+/// </details>
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Bundle {
+    SourceUrl(super::overrides::SourceUrl),
+    BundleDefinition(BundleDefinition),
+}
+impl ::std::convert::From<&Self> for Bundle {
+    fn from(value: &Bundle) -> Self {
+        value.clone()
+    }
+}
+impl ::std::convert::From<super::overrides::SourceUrl> for Bundle {
+    fn from(value: super::overrides::SourceUrl) -> Self {
+        Self::SourceUrl(value)
+    }
+}
+impl ::std::convert::From<BundleDefinition> for Bundle {
+    fn from(value: BundleDefinition) -> Self {
+        Self::BundleDefinition(value)
+    }
+}
+///バンドルの詳細な定義。
+///
+/// <details><summary>JSON schema</summary>
+///
+/// ```json
+///{
+///  "description": "バンドルの詳細な定義。",
+///  "type": "object",
+///  "required": [
+///    "url"
+///  ],
+///  "properties": {
+///    "encoding": {
+///      "description": "zipファイル内のファイル名の文字コード。UTF-8フラグが付いていないファイル名にのみ使用されます。\n省略した場合は、UTF-8として解釈できればUTF-8、できなければShift_JIS（CP932）として扱います。\n例: \"shift_jis\"",
+///      "type": "string"
+///    },
+///    "sha256": {
+///      "description": "ダウンロードしたバンドルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。\n例: \"1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30\"",
+///      "type": "string"
+///    },
+///    "url": {
+///      "description": "バンドルのURL。",
+///      "$ref": "#/$defs/SourceUrl"
+///    }
+///  }
+///}
+/// ```
+/// </details>
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct BundleDefinition {
+    /**zipファイル内のファイル名の文字コード。UTF-8フラグが付いていないファイル名にのみ使用されます。
+省略した場合は、UTF-8として解釈できればUTF-8、できなければShift_JIS（CP932）として扱います。
+例: "shift_jis"*/
+    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
+    pub encoding: ::std::option::Option<::std::string::String>,
+    /**ダウンロードしたバンドルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。
+例: "1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30"*/
+    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
+    pub sha256: ::std::option::Option<::std::string::String>,
+    ///バンドルのURL。
+    pub url: super::overrides::SourceUrl,
+}
+impl ::std::convert::From<&BundleDefinition> for BundleDefinition {
+    fn from(value: &BundleDefinition) -> Self {
+        value.clone()
+    }
+}
 /**データディレクトリ下の特定のサブディレクトリからの相対パス。
 
 以下のいずれかのディレクトリからの相対パスである必要があります：
@@ -125,6 +214,10 @@ for DataDirRelativePath {
 ///        "name"
 ///      ],
 ///      "properties": {
+///        "id": {
+///          "description": "親作品登録に使うID。`smXXXXXX`や`nmXXXXXX`の形式で指定してください。",
+///          "type": "string"
+///        },
 ///        "name": {
 ///          "description": "このユーザーコンテンツはニコニコ動画での親作品登録が必要であることを示します。",
 ///          "type": "string",
@@ -139,10 +232,6 @@ for DataDirRelativePath {
 ///          "description": "親作品登録が必須ではないことを示します。省略した場合、親作品登録は必須です。",
 ///          "type": "boolean"
 ///        },
-///        "id": {
-///          "description": "親作品登録に使うID。`smXXXXXX`や`nmXXXXXX`の形式で指定してください。",
-///          "type": "string"
-///        },
 ///        "text": {
 ///          "description": "利用規約の詳細。",
 ///          "$ref": "#/$defs/MaybeLocalizedString"
@@ -302,72 +391,6 @@ for LocalizedString {
 ///    "version"
 ///  ],
 ///  "properties": {
-///    "manifest_version": {
-///      "description": "マニフェストのバージョン。現在は1のみサポートしています。\n将来的にマニフェストの仕様が変わる可能性があるため、このフィールドを使用してバージョン管理を行います。\n破壊的変更が行われた場合にのみ、この値が増加します。",
-///      "type": "number",
-///      "const": 1,
-///      "x-rust-type": {
-///        "crate": "super",
-///        "path": "super::overrides::u64",
-///        "version": "*"
-///      }
-///    },
-///    "manifest_url": {
-///      "description": "このマニフェストへのURL。 ない場合は、アップデートチェックが行われません。",
-///      "$ref": "#/$defs/HttpUrl"
-///    },
-///    "id": {
-///      "description": "ユーザーコンテンツの一意な識別子。/^(?<author>[a-z0-9_]+)-(?<content_name>[a-z0-9_-]+)$/ にマッチし、かつ、\n`-`が連続しない文字列である必要があります。\nここで、authorは作者名、content_nameはユーザーコンテンツの名前を表します。\nコンテンツ名では`-`と`_`をどちらも使用することができ、それらは以下のように使用するべきです。\n- `-`は概念の区切りに使用する。\n- `_`は単語の一部に使用する。\n例えば、`sevenc_nanashi-aviutl2_rs-ffmpeg_output`は：\n- 「sevenc_nanashi」という作者の、\n- 「aviutl2_rs」というものに関する、\n- 「ffmpeg_output」というユーザーコンテンツ\nを表します。\nなお、`content_name`は1単語でも構いません。",
-///      "$ref": "#/$defs/ManifestId"
-///    },
-///    "name": {
-///      "description": "ユーザーコンテンツの名前。任意の文字列を指定できます。",
-///      "type": "string"
-///    },
-///    "summary": {
-///      "description": "ユーザーコンテンツの簡易的な説明。1行で収まるようにしてください。",
-///      "$ref": "#/$defs/MaybeLocalizedString"
-///    },
-///    "tags": {
-///      "description": "ユーザーコンテンツのタグ。\nタグは自由に追加できますが、以下のタグはUIで特別に扱われます：\n- `#alias`：エイリアス\n- `#alias-effect`：フィルタ効果エイリアス\n- `#alias-object`：オブジェクトエイリアス\n- `#default`：パレット\n- `#palette`：パレット（#defaultのエイリアス）\n- `#figure`：図形\n- `#language`：言語ファイル\n- `#plugin`：プラグイン\n- `#plugin-input`：入力プラグイン\n- `#plugin-output`：出力プラグイン\n- `#script`：スクリプト\n- `#script-anm`：アニメーション効果スクリプト\n- `#script-obj`：カスタムオブジェクトスクリプト\n- `#script-cam`：カメラ制御スクリプト\n- `#script-scn`：シーンチェンジスクリプト\n- `#script-tra`：トラックバー移動方法スクリプト\n- `#theme`：テーマ\n- `#transition`：トランジション",
-///      "type": "array",
-///      "items": {
-///        "$ref": "#/$defs/MaybeLocalizedString"
-///      }
-///    },
-///    "version": {
-///      "description": "ユーザーコンテンツのバージョン。`X.Y.Z(-.+)?`の形式に従う必要があります。\n例: \"1.0.0\", \"0.1.0\", \"2.3.4-beta\"",
-///      "$ref": "#/$defs/Version"
-///    },
-///    "version_number": {
-///      "description": "ユーザーコンテンツのバージョン番号。",
-///      "$ref": "#/$defs/Uint"
-///    },
-///    "previous_versions": {
-///      "description": "過去のバージョンのマニフェストへのURL。",
-///      "type": "array",
-///      "items": {
-///        "type": "object",
-///        "required": [
-///          "url",
-///          "version"
-///        ],
-///        "properties": {
-///          "version": {
-///            "description": "このバージョンのバージョン。",
-///            "$ref": "#/$defs/Version"
-///          },
-///          "version_number": {
-///            "description": "このバージョンのバージョン番号。",
-///            "$ref": "#/$defs/Uint"
-///          },
-///          "url": {
-///            "description": "マニフェストへのURL。",
-///            "$ref": "#/$defs/HttpUrl"
-///          }
-///        }
-///      }
-///    },
 ///    "authors": {
 ///      "description": "ユーザーコンテンツの作者。",
 ///      "type": "array",
@@ -387,17 +410,14 @@ for LocalizedString {
 ///      },
 ///      "minItems": 1
 ///    },
-///    "license": {
-///      "description": "ユーザーコンテンツを使用する際の利用規約。\nこのユーザーコンテンツを使用したときの規約のみを記述してください。（例えば、再配布の規約などは含めないでください）",
-///      "$ref": "#/$defs/License"
-///    },
-///    "homepage": {
-///      "description": "ユーザーコンテンツのホームページ。",
-///      "type": "string"
-///    },
-///    "description": {
-///      "description": "ユーザーコンテンツの説明。Markdown形式で記述できます。",
-///      "$ref": "#/$defs/MaybeLocalizedString"
+///    "bundles": {
+///      "description": "バンドルを定義します。\n複数ファイルをまとめたアーカイブをダウンロードし、その中から必要なファイルを取り出すために使用します。\nバンドル名をキー、そのバンドルのURLを値とするオブジェクトです。\nバンドル名は`[a-z0-9_]+`にマッチする必要があります。\n値にはURLを直接指定するか、SHA256ハッシュ値などを含めたオブジェクトを指定します。\n形式は拡張子ではなく、ファイルの内容から判定されます。\n今現在、以下のアーカイブ形式がサポートされています：\n- zip（`.zip`）\n- tar（`.tar`）\n- tar.gz（`.tar.gz`または`.tgz`）\n- tar.xz（`.tar.xz`または`.txz`）\n- tar.zst（`.tar.zst`）\n- 7z（`.7z`）",
+///      "$ref": "#/$defs/RecordBundle",
+///      "x-rust-type": {
+///        "crate": "super",
+///        "path": "super::overrides::Bundles",
+///        "version": "*"
+///      }
 ///    },
 ///    "changelogs": {
 ///      "description": "過去のバージョンの変更履歴。",
@@ -409,6 +429,10 @@ for LocalizedString {
 ///          "version"
 ///        ],
 ///        "properties": {
+///          "changes": {
+///            "description": "変更内容。Markdown形式で記述できます。",
+///            "$ref": "#/$defs/MaybeLocalizedString"
+///          },
 ///          "version": {
 ///            "description": "変更履歴のバージョン。`version`と同じ形式である必要があります。",
 ///            "$ref": "#/$defs/Version"
@@ -416,55 +440,37 @@ for LocalizedString {
 ///          "version_number": {
 ///            "description": "変更履歴のバージョン番号。`version_number`と同じ形式である必要があります。",
 ///            "$ref": "#/$defs/Uint"
-///          },
-///          "changes": {
-///            "description": "変更内容。Markdown形式で記述できます。",
-///            "$ref": "#/$defs/MaybeLocalizedString"
 ///          }
 ///        }
 ///      }
 ///    },
-///    "bundles": {
-///      "description": "バンドルを定義します。\n複数ファイルをまとめたアーカイブをダウンロードし、その中から必要なファイルを取り出すために使用します。\nバンドル名をキー、そのバンドルのURLを値とするオブジェクトです。\nバンドル名は`[a-z0-9_]+`にマッチする必要があります。\n今現在、以下のアーカイブ形式がサポートされています：\n- zip（`.zip`）\n- tar.gz（`.tar.gz`または`.tgz`）",
-///      "$ref": "#/$defs/RecordHttpUrl",
-///      "x-rust-type": {
-///        "crate": "super",
-///        "path": "super::overrides::Bundles",
-///        "version": "*"
-///      }
-///    },
-///    "resources": {
-///      "description": "ユーザーコンテンツのダウンロードURLと、そのインストール先の一覧。",
+///    "configurations": {
+///      "description": "このユーザーコンテンツが設定の永続化に使用するファイル。\n環境のエクスポート時にこれらのファイルが含まれます。",
 ///      "type": "array",
 ///      "items": {
 ///        "type": "object",
 ///        "required": [
-///          "destination",
-///          "source"
+///          "path"
 ///        ],
 ///        "properties": {
-///          "source": {
-///            "description": "このファイルの取得元。\n\n- `http://`または`https://` で始まるURL。\n- `bundle://{bundle_name}/{path}` の形式のURL。{bundle_name}はバンドル名、{path}はそのバンドル内のパスを表します。\nディレクトリを展開する場合はURLの末尾が`/`で終わる必要があります。これが一致していない場合はエラーになります。\nディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。",
-///            "$ref": "#/$defs/SourceUrl"
-///          },
-///          "destination": {
-///            "description": "ダウンロードしたファイルのインストール先。",
+///          "path": {
+///            "description": "このファイルのパス。ディレクトリを指定することもできます。",
 ///            "$ref": "#/$defs/DataDirRelativePath"
 ///          },
 ///          "scope": {
 ///            "description": "このファイルのスコープを指定します。",
 ///            "type": "string",
 ///            "pattern": "^[a-z0-9_-]+$"
-///          },
-///          "sha256": {
-///            "description": "ダウンロードしたファイルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。\n例: \"1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30\"",
-///            "type": "string"
 ///          }
 ///        }
 ///      }
 ///    },
-///    "configurations": {
-///      "description": "このユーザーコンテンツが設定の永続化に使用するファイル。\n環境のエクスポート時にこれらのファイルが含まれます。",
+///    "description": {
+///      "description": "ユーザーコンテンツの説明。Markdown形式で記述できます。",
+///      "$ref": "#/$defs/MaybeLocalizedString"
+///    },
+///    "disposables": {
+///      "description": "このユーザーコンテンツに関する、削除可能なファイルの一覧。\nユーザーがこのユーザーコンテンツを削除したときに、これらのファイルも削除されます。\nまた、環境のエクスポート時にはこれらのファイルは含まれません。\n\nキャッシュファイルやログファイルなど、一時的なファイルを指定してください。",
 ///      "type": "array",
 ///      "items": {
 ///        "type": "object",
@@ -475,34 +481,158 @@ for LocalizedString {
 ///          "path": {
 ///            "description": "このファイルのパス。ディレクトリを指定することもできます。",
 ///            "$ref": "#/$defs/DataDirRelativePath"
+///          }
+///        }
+///      }
+///    },
+///    "homepage": {
+///      "description": "ユーザーコンテンツのホームページ。",
+///      "type": "string"
+///    },
+///    "id": {
+///      "description": "ユーザーコンテンツの一意な識別子。/^(?<author>[a-z0-9_]+)-(?<content_name>[a-z0-9_-]+)$/ にマッチし、かつ、\n`-`が連続しない文字列である必要があります。\nここで、authorは作者名、content_nameはユーザーコンテンツの名前を表します。\nコンテンツ名では`-`と`_`をどちらも使用することができ、それらは以下のように使用するべきです。\n- `-`は概念の区切りに使用する。\n- `_`は単語の一部に使用する。\n例えば、`sevenc_nanashi-aviutl2_rs-ffmpeg_output`は：\n- 「sevenc_nanashi」という作者の、\n- 「aviutl2_rs」というものに関する、\n- 「ffmpeg_output」というユーザーコンテンツ\nを表します。\nなお、`content_name`は1単語でも構いません。",
+///      "$ref": "#/$defs/ManifestId"
+///    },
+///    "license": {
+///      "description": "ユーザーコンテンツを使用する際の利用規約。\nこのユーザーコンテンツを使用したときの規約のみを記述してください。（例えば、再配布の規約などは含めないでください）",
+///      "$ref": "#/$defs/License"
+///    },
+///    "manifest_url": {
+///      "description": "このマニフェストへのURL。 ない場合は、アップデートチェックが行われません。",
+///      "$ref": "#/$defs/HttpUrl"
+///    },
+///    "manifest_version": {
+///      "description": "マニフェストのバージョン。現在は1のみサポートしています。\n将来的にマニフェストの仕様が変わる可能性があるため、このフィールドを使用してバージョン管理を行います。\n破壊的変更が行われた場合にのみ、この値が増加します。",
+///      "type": "number",
+///      "const": 1,
+///      "x-rust-type": {
+///        "crate": "super",
+///        "path": "super::overrides::u64",
+///        "version": "*"
+///      }
+///    },
+///    "name": {
+///      "description": "ユーザーコンテンツの名前。任意の文字列を指定できます。",
+///      "type": "string"
+///    },
+///    "previous_versions": {
+///      "description": "過去のバージョンのマニフェストへのURL。",
+///      "type": "array",
+///      "items": {
+///        "type": "object",
+///        "required": [
+///          "url",
+///          "version"
+///        ],
+///        "properties": {
+///          "url": {
+///            "description": "マニフェストへのURL。",
+///            "$ref": "#/$defs/HttpUrl"
 ///          },
-///          "scope": {
-///            "description": "このファイルのスコープを指定します。",
-///            "type": "string",
-///            "pattern": "^[a-z0-9_-]+$"
+///          "version": {
+///            "description": "このバージョンのバージョン。",
+///            "$ref": "#/$defs/Version"
+///          },
+///          "version_number": {
+///            "description": "このバージョンのバージョン番号。",
+///            "$ref": "#/$defs/Uint"
 ///          }
 ///        }
 ///      }
 ///    },
-///    "disposables": {
-///      "description": "このユーザーコンテンツに関する、削除可能なファイルの一覧。\nユーザーがこのユーザーコンテンツを削除したときに、これらのファイルも削除されます。\nまた、環境のエクスポート時にはこれらのファイルは含まれません。\n\nキャッシュファイルやログファイルなど、一時的なファイルを指定してください。",
+///    "resources": {
+///      "description": "ユーザーコンテンツのダウンロードURLと、そのインストール先の一覧。",
 ///      "type": "array",
 ///      "items": {
 ///        "type": "object",
 ///        "required": [
-///          "path"
+///          "destination",
+///          "source"
 ///        ],
 ///        "properties": {
-///          "path": {
-///            "description": "このファイルのパス。ディレクトリを指定することもできます。",
+///          "destination": {
+///            "description": "ダウンロードしたファイルのインストール先。",
 ///            "$ref": "#/$defs/DataDirRelativePath"
+///          },
+///          "glob": {
+///            "description": "`true`の場合、`bundle://`の{path}をglobパターン（`*`、`**`、`?`、`[...]`、`{a,b}`）として扱います。\n一致した全てのファイルが、パターンを含む最初の階層より前を取り除いたパスで展開されます。インストール先はディレクトリである必要があります。\n省略した場合は、`[`や`*`を含むパスもそのままのパスとして扱います。",
+///            "type": "boolean"
+///          },
+///          "scope": {
+///            "description": "このファイルのスコープを指定します。",
+///            "type": "string",
+///            "pattern": "^[a-z0-9_-]+$"
+///          },
+///          "sha256": {
+///            "description": "ダウンロードしたファイルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。\n`bundle://`の場合は、展開後のファイルのハッシュ値を指定してください。ディレクトリを展開する場合は指定できません。\n例: \"1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30\"",
+///            "type": "string"
+///          },
+///          "source": {
+///            "description": "このファイルの取得元。\n\n- `http://`または`https://` で始まるURL。\n- `bundle://{bundle_name}/{path}` の形式のURL。{bundle_name}はバンドル名、{path}はそのバンドル内のパスを表します。\nディレクトリを展開する場合はURLの末尾が`/`で終わる必要があります。これが一致していない場合はエラーになります。\nディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。`glob`を指定した場合は、{path}をglobパターンとして扱います。",
+///            "$ref": "#/$defs/SourceUrl"
+///          },
+///          "strip_components": {
+///            "description": "`bundle://`でディレクトリやglobパターンを展開する場合に、展開するパスの先頭から取り除く階層の数。\n例えば、バンドル内の`foo-1.0/script.anm2`を`bundle://my_bundle/`と`strip_components: 1`で展開すると、`script.anm2`として配置されます。\n階層がこの数以下のファイルは展開されません。",
+///            "$ref": "#/$defs/Uint"
 ///          }
 ///        }
 ///      }
+///    },
+///    "summary": {
+///      "description": "ユーザーコンテンツの簡易的な説明。1行で収まるようにしてください。",
+///      "$ref": "#/$defs/MaybeLocalizedString"
+///    },
+///    "tags": {
+///      "description": "ユーザーコンテンツのタグ。\nタグは自由に追加できますが、以下のタグはUIで特別に扱われます：\n- `#alias`：エイリアス\n- `#alias-effect`：フィルタ効果エイリアス\n- `#alias-object`：オブジェクトエイリアス\n- `#default`：パレット\n- `#palette`：パレット（#defaultのエイリアス）\n- `#figure`：図形\n- `#language`：言語ファイル\n- `#plugin`：プラグイン\n- `#plugin-input`：入力プラグイン\n- `#plugin-output`：出力プラグイン\n- `#script`：スクリプト\n- `#script-anm`：アニメーション効果スクリプト\n- `#script-obj`：カスタムオブジェクトスクリプト\n- `#script-cam`：カメラ制御スクリプト\n- `#script-scn`：シーンチェンジスクリプト\n- `#script-tra`：トラックバー移動方法スクリプト\n- `#theme`：テーマ\n- `#transition`：トランジション",
+///      "type": "array",
+///      "items": {
+///        "$ref": "#/$defs/MaybeLocalizedString"
+///      }
+///    },
+///    "version": {
+///      "description": "ユーザーコンテンツのバージョン。`X.Y.Z(-.+)?`の形式に従う必要があります。\n例: \"1.0.0\", \"0.1.0\", \"2.3.4-beta\"",
+///      "$ref": "#/$defs/Version"
+///    },
+///    "version_number": {
+///      "description": "ユーザーコンテンツのバージョン番号。",
+///      "$ref": "#/$defs/Uint"
 ///    }
 ///  },
-///  "$schema": "https://json-schema.org/draft/2020-12/schema",
 ///  "$defs": {
+///    "Bundle": {
+///      "anyOf": [
+///        {
+///          "$ref": "#/$defs/SourceUrl",
+///          "title": "Url"
+///        },
+///        {
+///          "$ref": "#/$defs/BundleDefinition",
+///          "title": "Detailed"
+///        }
+///      ],
+///      "description": "バンドルの取得元。URLを直接指定するか、オブジェクトで詳細を指定します。\n`bundle://{bundle_name}/{path}`を指定すると、他のバンドル内のアーカイブをバンドルとして使えます。"
+///    },
+///    "BundleDefinition": {
+///      "description": "バンドルの詳細な定義。",
+///      "properties": {
+///        "encoding": {
+///          "description": "zipファイル内のファイル名の文字コード。UTF-8フラグが付いていないファイル名にのみ使用されます。\n省略した場合は、UTF-8として解釈できればUTF-8、できなければShift_JIS（CP932）として扱います。\n例: \"shift_jis\"",
+///          "type": "string"
+///        },
+///        "sha256": {
+///          "description": "ダウンロードしたバンドルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。\n例: \"1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30\"",
+///          "type": "string"
+///        },
+///        "url": {
+///          "$ref": "#/$defs/SourceUrl",
+///          "description": "バンドルのURL。"
+///        }
+///      },
+///      "required": [
+///        "url"
+///      ],
+///      "type": "object"
+///    },
 ///    "DataDirRelativePath": {
 ///      "description": "データディレクトリ下の特定のサブディレクトリからの相対パス。\n\n以下のいずれかのディレクトリからの相対パスである必要があります：\n- `$alias`：データディレクトリ/Alias 。\n- `$default`：データディレクトリ/Default 。\n- `$palette`：データディレクトリ/Default 。$defaultのエイリアスです。\n- `$figure`：データディレクトリ/Figure 。\n- `$language`：データディレクトリ/Language 。\n- `$plugin`：データディレクトリ/Plugin 。\n- `$script`：データディレクトリ/Script 。\n- `$transition`：データディレクトリ/Transition 。\n- `$theme`：データディレクトリ/au2ec/themes 。\n- `$data`：データディレクトリ自体。これは最終手段としてのみ使用してください。\n\nまた、`..`は含めることができません。",
 ///      "pattern": "^\\$(alias|default|palette|figure|language|plugin|script|transition|theme|data)/(?!.*\\.\\./).*$",
@@ -650,11 +780,11 @@ for LocalizedString {
 ///      ],
 ///      "description": "多言語対応文字列または単一言語文字列。\n例1：\"Hello\"\n例2：{ ja: \"こんにちは\", en: \"Hello\" }"
 ///    },
-///    "RecordHttpUrl": {
+///    "RecordBundle": {
 ///      "properties": {},
 ///      "type": "object",
 ///      "unevaluatedProperties": {
-///        "$ref": "#/$defs/HttpUrl"
+///        "$ref": "#/$defs/Bundle"
 ///      }
 ///    },
 ///    "SourceUrl": {
@@ -682,7 +812,8 @@ for LocalizedString {
 ///      "pattern": "^([0-9]+)\\.([0-9]+)\\.([0-9]+)(-[0-9A-Za-z-.]+)?$",
 ///      "type": "string"
 ///    }
-///  }
+///  },
+///  "$schema": "https://json-schema.org/draft/2020-12/schema"
 ///}
 /// ```
 /// </details>
@@ -694,9 +825,15 @@ pub struct Manifest {
 複数ファイルをまとめたアーカイブをダウンロードし、その中から必要なファイルを取り出すために使用します。
 バンドル名をキー、そのバンドルのURLを値とするオブジェクトです。
 バンドル名は`[a-z0-9_]+`にマッチする必要があります。
+値にはURLを直接指定するか、SHA256ハッシュ値などを含めたオブジェクトを指定します。
+形式は拡張子ではなく、ファイルの内容から判定されます。
 今現在、以下のアーカイブ形式がサポートされています：
 - zip（`.zip`）
-- tar.gz（`.tar.gz`または`.tgz`）*/
+- tar（`.tar`）
+- tar.gz（`.tar.gz`または`.tgz`）
+- tar.xz（`.tar.xz`または`.txz`）
+- tar.zst（`.tar.zst`）
+- 7z（`.7z`）*/
     #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
     pub bundles: ::std::option::Option<super::overrides::Bundles>,
     ///過去のバージョンの変更履歴。
@@ -829,6 +966,10 @@ impl ::std::convert::From<&ManifestAuthorsItem> for ManifestAuthorsItem {
 ///    "version"
 ///  ],
 ///  "properties": {
+///    "changes": {
+///      "description": "変更内容。Markdown形式で記述できます。",
+///      "$ref": "#/$defs/MaybeLocalizedString"
+///    },
 ///    "version": {
 ///      "description": "変更履歴のバージョン。`version`と同じ形式である必要があります。",
 ///      "$ref": "#/$defs/Version"
@@ -836,10 +977,6 @@ impl ::std::convert::From<&ManifestAuthorsItem> for ManifestAuthorsItem {
 ///    "version_number": {
 ///      "description": "変更履歴のバージョン番号。`version_number`と同じ形式である必要があります。",
 ///      "$ref": "#/$defs/Uint"
-///    },
-///    "changes": {
-///      "description": "変更内容。Markdown形式で記述できます。",
-///      "$ref": "#/$defs/MaybeLocalizedString"
 ///    }
 ///  }
 ///}
@@ -1107,6 +1244,10 @@ impl<'de> ::serde::Deserialize<'de> for ManifestId {
 ///    "version"
 ///  ],
 ///  "properties": {
+///    "url": {
+///      "description": "マニフェストへのURL。",
+///      "$ref": "#/$defs/HttpUrl"
+///    },
 ///    "version": {
 ///      "description": "このバージョンのバージョン。",
 ///      "$ref": "#/$defs/Version"
@@ -1114,10 +1255,6 @@ impl<'de> ::serde::Deserialize<'de> for ManifestId {
 ///    "version_number": {
 ///      "description": "このバージョンのバージョン番号。",
 ///      "$ref": "#/$defs/Uint"
-///    },
-///    "url": {
-///      "description": "マニフェストへのURL。",
-///      "$ref": "#/$defs/HttpUrl"
 ///    }
 ///  }
 ///}
@@ -1151,22 +1288,30 @@ for ManifestPreviousVersionsItem {
 ///    "source"
 ///  ],
 ///  "properties": {
-///    "source": {
-///      "description": "このファイルの取得元。\n\n- `http://`または`https://` で始まるURL。\n- `bundle://{bundle_name}/{path}` の形式のURL。{bundle_name}はバンドル名、{path}はそのバンドル内のパスを表します。\nディレクトリを展開する場合はURLの末尾が`/`で終わる必要があります。これが一致していない場合はエラーになります。\nディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。",
-///      "$ref": "#/$defs/SourceUrl"
-///    },
 ///    "destination": {
 ///      "description": "ダウンロードしたファイルのインストール先。",
 ///      "$ref": "#/$defs/DataDirRelativePath"
 ///    },
+///    "glob": {
+///      "description": "`true`の場合、`bundle://`の{path}をglobパターン（`*`、`**`、`?`、`[...]`、`{a,b}`）として扱います。\n一致した全てのファイルが、パターンを含む最初の階層より前を取り除いたパスで展開されます。インストール先はディレクトリである必要があります。\n省略した場合は、`[`や`*`を含むパスもそのままのパスとして扱います。",
+///      "type": "boolean"
+///    },
 ///    "scope": {
 ///      "description": "このファイルのスコープを指定します。",
 ///      "type": "string",
 ///      "pattern": "^[a-z0-9_-]+$"
 ///    },
 ///    "sha256": {
-///      "description": "ダウンロードしたファイルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。\n例: \"1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30\"",
+///      "description": "ダウンロードしたファイルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。\n`bundle://`の場合は、展開後のファイルのハッシュ値を指定してください。ディレクトリを展開する場合は指定できません。\n例: \"1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30\"",
 ///      "type": "string"
+///    },
+///    "source": {
+///      "description": "このファイルの取得元。\n\n- `http://`または`https://` で始まるURL。\n- `bundle://{bundle_name}/{path}` の形式のURL。{bundle_name}はバンドル名、{path}はそのバンドル内のパスを表します。\nディレクトリを展開する場合はURLの末尾が`/`で終わる必要があります。これが一致していない場合はエラーになります。\nディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。`glob`を指定した場合は、{path}をglobパターンとして扱います。",
+///      "$ref": "#/$defs/SourceUrl"
+///    },
+///    "strip_components": {
+///      "description": "`bundle://`でディレクトリやglobパターンを展開する場合に、展開するパスの先頭から取り除く階層の数。\n例えば、バンドル内の`foo-1.0/script.anm2`を`bundle://my_bundle/`と`strip_components: 1`で展開すると、`script.anm2`として配置されます。\n階層がこの数以下のファイルは展開されません。",
+///      "$ref": "#/$defs/Uint"
 ///    }
 ///  }
 ///}
@@ -1176,10 +1321,16 @@ for ManifestPreviousVersionsItem {
 pub struct ManifestResourcesItem {
     ///ダウンロードしたファイルのインストール先。
     pub destination: DataDirRelativePath,
+    /**`true`の場合、`bundle://`の{path}をglobパターン（`*`、`**`、`?`、`[...]`、`{a,b}`）として扱います。
+一致した全てのファイルが、パターンを含む最初の階層より前を取り除いたパスで展開されます。インストール先はディレクトリである必要があります。
+省略した場合は、`[`や`*`を含むパスもそのままのパスとして扱います。*/
+    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
+    pub glob: ::std::option::Option<bool>,
     ///このファイルのスコープを指定します。
     #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
     pub scope: ::std::option::Option<ManifestResourcesItemScope>,
     /**ダウンロードしたファイルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。
+`bundle://`の場合は、展開後のファイルのハッシュ値を指定してください。ディレクトリを展開する場合は指定できません。
 例: "1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30"*/
     #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
     pub sha256: ::std::option::Option<::std::string::String>,
@@ -1188,8 +1339,13 @@ pub struct ManifestResourcesItem {
 - `http://`または`https://` で始まるURL。
 - `bundle://{bundle_name}/{path}` の形式のURL。{bundle_name}はバンドル名、{path}はそのバンドル内のパスを表します。
 ディレクトリを展開する場合はURLの末尾が`/`で終わる必要があります。これが一致していない場合はエラーになります。
-ディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。*/
+ディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。`glob`を指定した場合は、{path}をglobパターンとして扱います。*/
     pub source: super::overrides::SourceUrl,
+    /**`bundle://`でディレクトリやglobパターンを展開する場合に、展開するパスの先頭から取り除く階層の数。
+例えば、バンドル内の`foo-1.0/script.anm2`を`bundle://my_bundle/`と`strip_components: 1`で展開すると、`script.anm2`として配置されます。
+階層がこの数以下のファイルは展開されません。*/
+    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
+    pub strip_components: ::std::option::Option<Uint>,
 }
 impl ::std::convert::From<&ManifestResourcesItem> for ManifestResourcesItem {
     fn from(value: &ManifestResourcesItem) -> Self {
@@ -1314,7 +1470,7 @@ impl ::std::convert::From<LocalizedString> for MaybeLocalizedString {
         Self::LocalizedString(value)
     }
 }
-///`RecordHttpUrl`
+///`RecordBundle`
 ///
 /// <details><summary>JSON schema</summary>
 ///
@@ -1322,35 +1478,35 @@ impl ::std::convert::From<LocalizedString> for MaybeLocalizedString {
 ///{
 ///  "type": "object",
 ///  "unevaluatedProperties": {
-///    "$ref": "#/$defs/HttpUrl"
+///    "$ref": "#/$defs/Bundle"
 ///  }
 ///}
 /// ```
 /// </details>
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
 #[serde(transparent)]
-pub struct RecordHttpUrl(
+pub struct RecordBundle(
     pub ::serde_json::Map<::std::string::String, ::serde_json::Value>,
 );
-impl ::std::ops::Deref for RecordHttpUrl {
+impl ::std::ops::Deref for RecordBundle {
     type Target = ::serde_json::Map<::std::string::String, ::serde_json::Value>;
     fn deref(&self) -> &::serde_json::Map<::std::string::String, ::serde_json::Value> {
         &self.0
     }
 }
-impl ::std::convert::From<RecordHttpUrl>
+impl ::std::convert::From<RecordBundle>
 for ::serde_json::Map<::std::string::String, ::serde_json::Value> {
-    fn from(value: RecordHttpUrl) -> Self {
+    fn from(value: RecordBundle) -> Self {
         value.0
     }
 }
-impl ::std::convert::From<&RecordHttpUrl> for RecordHttpUrl {
-    fn from(value: &RecordHttpUrl) -> Self {
+impl ::std::convert::From<&RecordBundle> for RecordBundle {
+    fn from(value: &RecordBundle) -> Self {
         value.clone()
     }
 }
 impl ::std::convert::From<::serde_json::Map<::std::string::String, ::serde_json::Value>>
-for RecordHttpUrl {
+for RecordBundle {
     fn from(
         value: ::serde_json::Map<::std::string::String, ::serde_json::Value>,
     ) -> Self {
@@ -1373,28 +1529,6 @@ for RecordHttpUrl {
 ///    "summary"
 ///  ],
 ///  "properties": {
-///    "registry_version": {
-///      "description": "レジストリのバージョン。現在は1のみサポートしています。\n破壊的変更が行われた場合にのみ、この値が増加します。",
-///      "type": "number",
-///      "const": 1,
-///      "x-rust-type": {
-///        "crate": "std",
-///        "path": "std::u64",
-///        "version": "*"
-///      }
-///    },
-///    "name": {
-///      "description": "レジストリの名前。",
-///      "type": "string"
-///    },
-///    "summary": {
-///      "description": "このレジストリの説明。",
-///      "$ref": "#/$defs/MaybeLocalizedString"
-///    },
-///    "homepage": {
-///      "description": "このレジストリのホームページ。",
-///      "$ref": "#/$defs/HttpUrl"
-///    },
 ///    "contents": {
 ///      "description": "このレジストリに含まれるユーザーコンテンツの一覧。",
 ///      "type": "array",
@@ -1411,6 +1545,45 @@ for RecordHttpUrl {
 ///          "version"
 ///        ],
 ///        "properties": {
+///          "authors": {
+///            "description": "ユーザーコンテンツの作者。",
+///            "type": "array",
+///            "items": {
+///              "type": "object",
+///              "required": [
+///                "name"
+///              ],
+///              "properties": {
+///                "name": {
+///                  "$ref": "#/$defs/MaybeLocalizedString"
+///                },
+///                "url": {
+///                  "$ref": "#/$defs/HttpUrl"
+///                }
+///              }
+///            },
+///            "minItems": 1
+///          },
+///          "description": {
+///            "description": "ユーザーコンテンツの説明。Markdown形式で記述できます。",
+///            "$ref": "#/$defs/MaybeLocalizedString"
+///          },
+///          "homepage": {
+///            "description": "ユーザーコンテンツのホームページ。",
+///            "type": "string"
+///          },
+///          "id": {
+///            "description": "ユーザーコンテンツの一意な識別子。/^(?<author>[a-z0-9_]+)-(?<content_name>[a-z0-9_-]+)$/ にマッチし、かつ、\n`-`が連続しない文字列である必要があります。\nここで、authorは作者名、content_nameはユーザーコンテンツの名前を表します。\nコンテンツ名では`-`と`_`をどちらも使用することができ、それらは以下のように使用するべきです。\n- `-`は概念の区切りに使用する。\n- `_`は単語の一部に使用する。\n例えば、`sevenc_nanashi-aviutl2_rs-ffmpeg_output`は：\n- 「sevenc_nanashi」という作者の、\n- 「aviutl2_rs」というものに関する、\n- 「ffmpeg_output」というユーザーコンテンツ\nを表します。\nなお、`content_name`は1単語でも構いません。",
+///            "$ref": "#/$defs/ManifestId"
+///          },
+///          "license": {
+///            "description": "ユーザーコンテンツを使用する際の利用規約。\nこのユーザーコンテンツを使用したときの規約のみを記述してください。（例えば、再配布の規約などは含めないでください）",
+///            "$ref": "#/$defs/License"
+///          },
+///          "manifest_url": {
+///            "description": "このユーザーコンテンツのマニフェストへのURL。\n単体のマニフェストとは違い、このフィールドは必須です。",
+///            "type": "string"
+///          },
 ///          "manifest_version": {
 ///            "description": "マニフェストのバージョン。現在は1のみサポートしています。\n将来的にマニフェストの仕様が変わる可能性があるため、このフィールドを使用してバージョン管理を行います。\n破壊的変更が行われた場合にのみ、この値が増加します。",
 ///            "type": "number",
@@ -1421,10 +1594,6 @@ for RecordHttpUrl {
 ///              "version": "*"
 ///            }
 ///          },
-///          "id": {
-///            "description": "ユーザーコンテンツの一意な識別子。/^(?<author>[a-z0-9_]+)-(?<content_name>[a-z0-9_-]+)$/ にマッチし、かつ、\n`-`が連続しない文字列である必要があります。\nここで、authorは作者名、content_nameはユーザーコンテンツの名前を表します。\nコンテンツ名では`-`と`_`をどちらも使用することができ、それらは以下のように使用するべきです。\n- `-`は概念の区切りに使用する。\n- `_`は単語の一部に使用する。\n例えば、`sevenc_nanashi-aviutl2_rs-ffmpeg_output`は：\n- 「sevenc_nanashi」という作者の、\n- 「aviutl2_rs」というものに関する、\n- 「ffmpeg_output」というユーザーコンテンツ\nを表します。\nなお、`content_name`は1単語でも構いません。",
-///            "$ref": "#/$defs/ManifestId"
-///          },
 ///          "name": {
 ///            "description": "ユーザーコンテンツの名前。任意の文字列を指定できます。",
 ///            "type": "string"
@@ -1447,47 +1616,33 @@ for RecordHttpUrl {
 ///          "version_number": {
 ///            "description": "ユーザーコンテンツのバージョン番号。",
 ///            "$ref": "#/$defs/Uint"
-///          },
-///          "authors": {
-///            "description": "ユーザーコンテンツの作者。",
-///            "type": "array",
-///            "items": {
-///              "type": "object",
-///              "required": [
-///                "name"
-///              ],
-///              "properties": {
-///                "name": {
-///                  "$ref": "#/$defs/MaybeLocalizedString"
-///                },
-///                "url": {
-///                  "$ref": "#/$defs/HttpUrl"
-///                }
-///              }
-///            },
-///            "minItems": 1
-///          },
-///          "license": {
-///            "description": "ユーザーコンテンツを使用する際の利用規約。\nこのユーザーコンテンツを使用したときの規約のみを記述してください。（例えば、再配布の規約などは含めないでください）",
-///            "$ref": "#/$defs/License"
-///          },
-///          "homepage": {
-///            "description": "ユーザーコンテンツのホームページ。",
-///            "type": "string"
-///          },
-///          "description": {
-///            "description": "ユーザーコンテンツの説明。Markdown形式で記述できます。",
-///            "$ref": "#/$defs/MaybeLocalizedString"
-///          },
-///          "manifest_url": {
-///            "description": "このユーザーコンテンツのマニフェストへのURL。\n単体のマニフェストとは違い、このフィールドは必須です。",
-///            "type": "string"
 ///          }
 ///        }
 ///      }
+///    },
+///    "homepage": {
+///      "description": "このレジストリのホームページ。",
+///      "$ref": "#/$defs/HttpUrl"
+///    },
+///    "name": {
+///      "description": "レジストリの名前。",
+///      "type": "string"
+///    },
+///    "registry_version": {
+///      "description": "レジストリのバージョン。現在は1のみサポートしています。\n破壊的変更が行われた場合にのみ、この値が増加します。",
+///      "type": "number",
+///      "const": 1,
+///      "x-rust-type": {
+///        "crate": "std",
+///        "path": "std::u64",
+///        "version": "*"
+///      }
+///    },
+///    "summary": {
+///      "description": "このレジストリの説明。",
+///      "$ref": "#/$defs/MaybeLocalizedString"
 ///    }
 ///  },
-///  "$schema": "https://json-schema.org/draft/2020-12/schema",
 ///  "$defs": {
 ///    "HttpUrl": {
 ///      "description": "http・httpsのURL。",
@@ -1640,7 +1795,8 @@ for RecordHttpUrl {
 ///      "pattern": "^([0-9]+)\\.([0-9]+)\\.([0-9]+)(-[0-9A-Za-z-.]+)?$",
 ///      "type": "string"
 ///    }
-///  }
+///  },
+///  "$schema": "https://json-schema.org/draft/2020-12/schema"
 ///}
 /// ```
 /// </details>
@@ -1680,6 +1836,45 @@ impl ::std::convert::From<&Registry> for Registry {
 ///    "version"
 ///  ],
 ///  "properties": {
+///    "authors": {
+///      "description": "ユーザーコンテンツの作者。",
+///      "type": "array",
+///      "items": {
+///        "type": "object",
+///        "required": [
+///          "name"
+///        ],
+///        "properties": {
+///          "name": {
+///            "$ref": "#/$defs/MaybeLocalizedString"
+///          },
+///          "url": {
+///            "$ref": "#/$defs/HttpUrl"
+///          }
+///        }
+///      },
+///      "minItems": 1
+///    },
+///    "description": {
+///      "description": "ユーザーコンテンツの説明。Markdown形式で記述できます。",
+///      "$ref": "#/$defs/MaybeLocalizedString"
+///    },
+///    "homepage": {
+///      "description": "ユーザーコンテンツのホームページ。",
+///      "type": "string"
+///    },
+///    "id": {
+///      "description": "ユーザーコンテンツの一意な識別子。/^(?<author>[a-z0-9_]+)-(?<content_name>[a-z0-9_-]+)$/ にマッチし、かつ、\n`-`が連続しない文字列である必要があります。\nここで、authorは作者名、content_nameはユーザーコンテンツの名前を表します。\nコンテンツ名では`-`と`_`をどちらも使用することができ、それらは以下のように使用するべきです。\n- `-`は概念の区切りに使用する。\n- `_`は単語の一部に使用する。\n例えば、`sevenc_nanashi-aviutl2_rs-ffmpeg_output`は：\n- 「sevenc_nanashi」という作者の、\n- 「aviutl2_rs」というものに関する、\n- 「ffmpeg_output」というユーザーコンテンツ\nを表します。\nなお、`content_name`は1単語でも構いません。",
+///      "$ref": "#/$defs/ManifestId"
+///    },
+///    "license": {
+///      "description": "ユーザーコンテンツを使用する際の利用規約。\nこのユーザーコンテンツを使用したときの規約のみを記述してください。（例えば、再配布の規約などは含めないでください）",
+///      "$ref": "#/$defs/License"
+///    },
+///    "manifest_url": {
+///      "description": "このユーザーコンテンツのマニフェストへのURL。\n単体のマニフェストとは違い、このフィールドは必須です。",
+///      "type": "string"
+///    },
 ///    "manifest_version": {
 ///      "description": "マニフェストのバージョン。現在は1のみサポートしています。\n将来的にマニフェストの仕様が変わる可能性があるため、このフィールドを使用してバージョン管理を行います。\n破壊的変更が行われた場合にのみ、この値が増加します。",
 ///      "type": "number",
@@ -1690,10 +1885,6 @@ impl ::std::convert::From<&Registry> for Registry {
 ///        "version": "*"
 ///      }
 ///    },
-///    "id": {
-///      "description": "ユーザーコンテンツの一意な識別子。/^(?<author>[a-z0-9_]+)-(?<content_name>[a-z0-9_-]+)$/ にマッチし、かつ、\n`-`が連続しない文字列である必要があります。\nここで、authorは作者名、content_nameはユーザーコンテンツの名前を表します。\nコンテンツ名では`-`と`_`をどちらも使用することができ、それらは以下のように使用するべきです。\n- `-`は概念の区切りに使用する。\n- `_`は単語の一部に使用する。\n例えば、`sevenc_nanashi-aviutl2_rs-ffmpeg_output`は：\n- 「sevenc_nanashi」という作者の、\n- 「aviutl2_rs」というものに関する、\n- 「ffmpeg_output」というユーザーコンテンツ\nを表します。\nなお、`content_name`は1単語でも構いません。",
-///      "$ref": "#/$defs/ManifestId"
-///    },
 ///    "name": {
 ///      "description": "ユーザーコンテンツの名前。任意の文字列を指定できます。",
 ///      "type": "string"
@@ -1716,41 +1907,6 @@ impl ::std::convert::From<&Registry> for Registry {
 ///    "version_number": {
 ///      "description": "ユーザーコンテンツのバージョン番号。",
 ///      "$ref": "#/$defs/Uint"
-///    },
-///    "authors": {
-///      "description": "ユーザーコンテンツの作者。",
-///      "type": "array",
-///      "items": {
-///        "type": "object",
-///        "required": [
-///          "name"
-///        ],
-///        "properties": {
-///          "name": {
-///            "$ref": "#/$defs/MaybeLocalizedString"
-///          },
-///          "url": {
-///            "$ref": "#/$defs/HttpUrl"
-///          }
-///        }
-///      },
-///      "minItems": 1
-///    },
-///    "license": {
-///      "description": "ユーザーコンテンツを使用する際の利用規約。\nこのユーザーコンテンツを使用したときの規約のみを記述してください。（例えば、再配布の規約などは含めないでください）",
-///      "$ref": "#/$defs/License"
-///    },
-///    "homepage": {
-///      "description": "ユーザーコンテンツのホームページ。",
-///      "type": "string"
-///    },
-///    "description": {
-///      "description": "ユーザーコンテンツの説明。Markdown形式で記述できます。",
-///      "$ref": "#/$defs/MaybeLocalizedString"
-///    },
-///    "manifest_url": {
-///      "description": "このユーザーコンテンツのマニフェストへのURL。\n単体のマニフェストとは違い、このフィールドは必須です。",
-///      "type": "string"
 ///    }
 ///  }
 ///}
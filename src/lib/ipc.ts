@@ -115,13 +115,44 @@ export async function removeManifest(manifest: string): Promise<void> {
   return await invoke("remove_manifest", { manifest });
 }
 
+export interface Settings {
+  download_concurrency: number;
+  cache_max_size: number;
+  config_backup_max_count: number | null;
+  config_backup_max_age_days: number | null;
+  generation_max_count: number | null;
+}
+export async function getSettings(): Promise<Settings> {
+  return await invoke("get_settings");
+}
+export async function setSettings(settings: Settings): Promise<void> {
+  return await invoke("set_settings", { settings });
+}
+
+export interface CacheInfo {
+  num_entries: number;
+  total_size: number;
+  max_size: number;
+}
+export async function getCacheInfo(): Promise<CacheInfo> {
+  return await invoke("get_cache_info");
+}
+export async function clearCache(): Promise<void> {
+  return await invoke("clear_cache");
+}
+
 export interface Version {
   version: string;
   version_number?: number | undefined | null;
 }
+export interface InstalledManifest {
+  manifest: Manifest;
+  installed_at: string;
+  registry: string | null;
+}
 export interface ProfileStore {
   name: string;
-  contents: Record<string, Manifest>;
+  contents: Record<string, InstalledManifest>;
 }
 export async function getProfileStore(
   profileId: string,
@@ -129,21 +160,55 @@ export async function getProfileStore(
   return await invoke("get_profile_store", { profileId });
 }
 
+export type UntrackedFileResolution = "abort" | "overwrite" | "adopt";
+export interface UntrackedFile {
+  path: string;
+  manifest_id: string;
+}
 export interface InstallPlan {
   to_keep: Manifest[];
   to_update: [Manifest, Manifest][];
   to_install: Manifest[];
+  to_repair?: [Manifest, string[]][];
+  untracked: Record<string, UntrackedFileResolution>;
 }
 export async function planInstallation(
   profileId: string,
   desiredManifests: Manifest[],
+  resolutions?: Record<string, UntrackedFileResolution>,
 ): Promise<InstallPlan> {
   return await invoke("plan_installation", {
     profileId,
     desiredManifests,
+    resolutions,
   });
 }
 
+export interface AdoptResult {
+  adopted: string[];
+  unmatched: string[];
+}
+export async function scanAndAdopt(profileId: string): Promise<AdoptResult> {
+  return await invoke("scan_and_adopt", { profileId });
+}
+
+export interface OrphanFile {
+  path: string;
+  size: number;
+  modified: string | null;
+}
+export async function listOrphans(
+  profileId: string,
+): Promise<Record<string, OrphanFile[]>> {
+  return await invoke("list_orphans", { profileId });
+}
+export async function quarantineOrphans(
+  profileId: string,
+  paths: string[],
+): Promise<string> {
+  return await invoke("quarantine_orphans", { profileId, paths });
+}
+
 export type InstallProgress =
   | {
       type: "download";
@@ -153,6 +218,7 @@ export type InstallProgress =
         name: string;
         bytes: number;
         total_bytes: number | null;
+        resumed_from: number;
       };
     }
   | {
@@ -178,6 +244,22 @@ export type InstallProgress =
         num_files: number;
       };
     }
+  | {
+      type: "keep";
+      data: {
+        file: number;
+        num_files: number;
+        name: string;
+      };
+    }
+  | {
+      type: "replace";
+      data: {
+        file: number;
+        num_files: number;
+        name: string;
+      };
+    }
   | {
       type: "complete";
     }
@@ -187,6 +269,211 @@ export type InstallProgress =
     };
 
 export type InstallChannel = Channel<[string, InstallProgress]>;
+export interface VerifyResult {
+  missing: string[];
+  modified: string[];
+  extra: string[];
+}
+export async function verifyProfile(
+  profileId: string,
+  repair: boolean,
+): Promise<{
+  results: Record<string, VerifyResult>;
+  channel: InstallChannel;
+}> {
+  const channel: InstallChannel = new Channel();
+  const results: Record<string, VerifyResult> = await invoke("verify_profile", {
+    profileId,
+    repair,
+    ch: channel,
+  });
+  return { results, channel };
+}
+
+export interface ConfigBackup {
+  manifest_id: string;
+  version: string;
+  file_name: string;
+  created_at: string;
+  size: number;
+}
+export interface ConfigBackupEntry {
+  path: string;
+  size: number;
+}
+export async function listConfigBackups(
+  profileId: string,
+  manifestId: string | null,
+): Promise<ConfigBackup[]> {
+  return await invoke("list_config_backups", { profileId, manifestId });
+}
+export async function previewConfigBackup(
+  profileId: string,
+  manifestId: string,
+  fileName: string,
+): Promise<ConfigBackupEntry[]> {
+  return await invoke("preview_config_backup", {
+    profileId,
+    manifestId,
+    fileName,
+  });
+}
+export async function restoreConfigBackup(
+  profileId: string,
+  manifestId: string,
+  fileName: string,
+  targetProfileId: string | null,
+): Promise<string[]> {
+  return await invoke("restore_config_backup", {
+    profileId,
+    manifestId,
+    fileName,
+    targetProfileId,
+  });
+}
+
+export async function exportProfile(
+  profileId: string,
+  dest: string,
+): Promise<void> {
+  return await invoke("export_profile", { profileId, dest });
+}
+export async function importProfile(
+  archive: string,
+  name: string | null,
+  path: string,
+): Promise<{
+  profileId: string;
+  channel: InstallChannel;
+}> {
+  const channel: InstallChannel = new Channel();
+  const profileId: string = await invoke("import_profile", {
+    archive,
+    name,
+    path,
+    ch: channel,
+  });
+  return { profileId, channel };
+}
+
+export async function generateLockfile(profileId: string): Promise<string> {
+  return await invoke("generate_lockfile", { profileId });
+}
+export async function syncFromLockfile(
+  profileId: string,
+  lockfile: string | null,
+  resolutions?: Record<string, UntrackedFileResolution>,
+): Promise<{
+  plan: InstallPlan;
+  channel: InstallChannel;
+}> {
+  const channel: InstallChannel = new Channel();
+  const plan: InstallPlan = await invoke("sync_from_lockfile", {
+    profileId,
+    lockfile,
+    resolutions,
+    ch: channel,
+  });
+  return { plan, channel };
+}
+
+export interface GenerationSummary {
+  number: number;
+  created_at: string;
+  manifests: Record<string, string>;
+}
+export async function listGenerations(
+  profileId: string,
+): Promise<GenerationSummary[]> {
+  return await invoke("list_generations", { profileId });
+}
+export async function rollbackToGeneration(
+  profileId: string,
+  generation: number,
+  resolutions?: Record<string, UntrackedFileResolution>,
+): Promise<{
+  plan: InstallPlan;
+  channel: InstallChannel;
+}> {
+  const channel: InstallChannel = new Channel();
+  const plan: InstallPlan = await invoke("rollback_to_generation", {
+    profileId,
+    generation,
+    resolutions,
+    ch: channel,
+  });
+  return { plan, channel };
+}
+
+export interface DriftReport {
+  checked_at: string;
+  untracked: Record<string, OrphanFile[]>;
+  modified: Record<string, string[]>;
+  missing: Record<string, string[]>;
+  outdated: {
+    id: string;
+    installed: string;
+    upstream: string;
+  }[];
+  declared: {
+    not_installed: string[];
+    not_declared: string[];
+    version_mismatch: {
+      id: string;
+      declared: string;
+      installed: string;
+    }[];
+  } | null;
+}
+export async function detectDrift(
+  profileId: string,
+  options: {
+    lockfile?: string;
+    composition?: Manifest[];
+    checkUpstream?: boolean;
+  } = {},
+): Promise<DriftReport> {
+  return await invoke("detect_drift", {
+    profileId,
+    lockfile: options.lockfile ?? null,
+    composition: options.composition ?? null,
+    checkUpstream: options.checkUpstream ?? null,
+  });
+}
+
+export type FileOperation =
+  | {
+      type: "create";
+      manifest_id: string;
+      path: string;
+      size: number | null;
+    }
+  | {
+      type: "overwrite";
+      manifest_id: string;
+      path: string;
+      size: number | null;
+      previous_size: number | null;
+    }
+  | {
+      type: "delete";
+      manifest_id: string;
+      path: string;
+      size: number | null;
+    }
+  | {
+      type: "backup";
+      manifest_id: string;
+      path: string;
+      size: number | null;
+    };
+export async function dryRunInstallation(
+  profileId: string,
+  plan: InstallPlan,
+): Promise<FileOperation[]> {
+  return await invoke("dry_run_installation", { profileId, plan });
+}
+
 export async function performInstallation(
   profileId: string,
   plan: InstallPlan,
@@ -149,7 +149,7 @@ export interface Manifest {
      */
     changes: Single | Localized;
   }[];
-  bundles?: RecordHttpUrl;
+  bundles?: RecordBundle;
   /**
    * ユーザーコンテンツのダウンロードURLと、そのインストール先の一覧。
    */
@@ -160,7 +160,7 @@ export interface Manifest {
      * - `http://`または`https://` で始まるURL。
      * - `bundle://{bundle_name}/{path}` の形式のURL。{bundle_name}はバンドル名、{path}はそのバンドル内のパスを表します。
      * ディレクトリを展開する場合はURLの末尾が`/`で終わる必要があります。これが一致していない場合はエラーになります。
-     * ディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。
+     * ディレクトリを指定した場合は、そのディレクトリ内が再帰的に展開されます。`glob`を指定した場合は、{path}をglobパターンとして扱います。
      */
     source: string;
     /**
@@ -173,9 +173,22 @@ export interface Manifest {
     scope?: string;
     /**
      * ダウンロードしたファイルのSHA256ハッシュ値。省略可能ですが、指定することを推奨します。
+     * `bundle://`の場合は、展開後のファイルのハッシュ値を指定してください。ディレクトリを展開する場合は指定できません。
      * 例: "1e9211b2f7152fe7f1b4f4a3c972c8fb56845acd258f03694625d14ee516ec30"
      */
     sha256?: string;
+    /**
+     * `true`の場合、`bundle://`の{path}をglobパターン（`*`、`**`、`?`、`[...]`、`{a,b}`）として扱います。
+     * 一致した全てのファイルが、パターンを含む最初の階層より前を取り除いたパスで展開されます。インストール先はディレクトリである必要があります。
+     * 省略した場合は、`[`や`*`を含むパスもそのままのパスとして扱います。
+     */
+    glob?: boolean;
+    /**
+     * `bundle://`でディレクトリやglobパターンを展開する場合に、展開するパスの先頭から取り除く階層の数。
+     * 例えば、バンドル内の`foo-1.0/script.anm2`を`bundle://my_bundle/`と`strip_components: 1`で展開すると、`script.anm2`として配置されます。
+     * 階層がこの数以下のファイルは展開されません。
+     */
+    strip_components?: number;
   }[];
   /**
    * このユーザーコンテンツが設定の永続化に使用するファイル。
@@ -271,8 +284,14 @@ export interface Unknown {
  * 複数ファイルをまとめたアーカイブをダウンロードし、その中から必要なファイルを取り出すために使用します。
  * バンドル名をキー、そのバンドルのURLを値とするオブジェクトです。
  * バンドル名は`[a-z0-9_]+`にマッチする必要があります。
+ * 値にはURLを直接指定するか、SHA256ハッシュ値などを含めたオブジェクトを指定します。
+ * 形式は拡張子ではなく、ファイルの内容から判定されます。
  * 今現在、以下のアーカイブ形式がサポートされています：
  * - zip（`.zip`）
+ * - tar（`.tar`）
  * - tar.gz（`.tar.gz`または`.tgz`）
+ * - tar.xz（`.tar.xz`または`.txz`）
+ * - tar.zst（`.tar.zst`）
+ * - 7z（`.7z`）
  */
-export interface RecordHttpUrl {}
+export interface RecordBundle {}